@@ -0,0 +1,66 @@
+//! Compares the BVH against a linear scan over every object on a scene of 100k spheres.
+//!
+//! Run with `cargo bench --bench bvh`.
+
+use std::time::Instant;
+
+use gametest::base::{Color, Drawable, Ray};
+use gametest::bvh::Bvh;
+use gametest::material::{Material, SurfaceType};
+use gametest::objects::sphere::Sphere;
+use gametest::vector::Vector;
+
+const SPHERE_COUNT: usize = 100_000;
+const RAY_COUNT: usize = 2_000;
+
+/// xorshift64*, good enough to scatter spheres deterministically
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+fn linear_trace(ray: &Ray, objects: &[Box<dyn Drawable>]) -> Option<f64> {
+    objects.iter()
+        .filter_map(|object| object.intersect(ray))
+        .fold(None, |closest: Option<f64>, distance| Some(closest.map_or(distance, |c| c.min(distance))))
+}
+
+fn main() {
+    let mut random = Random(0x9E37_79B9_7F4A_7C15);
+    let mut objects: Vec<Box<dyn Drawable>> = Vec::with_capacity(SPHERE_COUNT);
+    for _ in 0..SPHERE_COUNT {
+        let center = Vector::new(random.next() * 200.0 - 100.0, random.next() * 200.0 - 100.0,
+                                 -random.next() * 200.0 - 10.0);
+        let material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        objects.push(Box::new(Sphere::new(center, 0.1 + random.next() * 0.4, material)));
+    }
+    let rays: Vec<Ray> = (0..RAY_COUNT)
+        .map(|_| Ray::from(Vector::zero(),
+                           Vector::new(random.next() - 0.5, random.next() - 0.5, -1.0).normalize()))
+        .collect();
+
+    let start = Instant::now();
+    let bvh = Bvh::new(&objects);
+    let build_time = start.elapsed();
+
+    let start = Instant::now();
+    let bvh_hits = rays.iter().filter(|ray| bvh.trace(ray, &objects).is_some()).count();
+    let bvh_time = start.elapsed();
+
+    let start = Instant::now();
+    let linear_hits = rays.iter().filter(|ray| linear_trace(ray, &objects).is_some()).count();
+    let linear_time = start.elapsed();
+
+    assert_eq!(bvh_hits, linear_hits);
+    println!("{} spheres, {} rays ({} hits)", SPHERE_COUNT, RAY_COUNT, bvh_hits);
+    println!("BVH build:   {:?}", build_time);
+    println!("BVH trace:   {:?}", bvh_time);
+    println!("linear scan: {:?}", linear_time);
+    println!("speedup:     {:.1}x", linear_time.as_secs_f64() / bvh_time.as_secs_f64());
+}
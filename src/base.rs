@@ -1,7 +1,10 @@
-use crate::scene::Scene;
+use std::ops;
+
 use crate::vector::Vector;
 use crate::material::Material;
+use crate::transform::Transform;
 
+#[derive(PartialEq, Debug, Clone)]
 pub struct Point2D {
     pub x: f64,
     pub y: f64,
@@ -10,6 +13,21 @@ pub struct Point2D {
 pub trait Intersectable {
     fn intersect(&self, ray: &Ray) -> Option<f64>;
     fn surface_normal(&self, hit_point: &Vector) -> Vector;
+    fn bounding_box(&self) -> BoundingBox;
+    /// Sorted `(entry, exit)` distances of all spans of the ray inside the object, including the
+    /// ones behind the ray origin. Only solids have an inside, surfaces like triangles report none.
+    fn intervals(&self, _ray: &Ray) -> Vec<(f64, f64)> {
+        Vec::new()
+    }
+    /// Area of the surface, for the shapes that `sample_surface` can pick points on.
+    fn surface_area(&self) -> Option<f64> {
+        None
+    }
+    /// Point on the surface and the normal there for `(u, v)` in `[0, 1)²`, spread uniformly over
+    /// the area, where the object is at `time`. Unbounded and composite objects cannot be sampled.
+    fn sample_surface(&self, _u: f64, _v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        None
+    }
 }
 
 pub trait Textureable {
@@ -17,61 +35,277 @@ pub trait Textureable {
     fn get_texture_color(&self, hit_point: &Vector) -> Color;
 }
 
-pub trait Drawable: Intersectable + Textureable {
+pub trait Drawable: Intersectable + Textureable + AsDrawable + Send + Sync {
     fn get_material(&self) -> &Material;
+    /// Intersects the ray, reporting the primitive that was hit. Objects made of other objects
+    /// return the hit part, possibly with a transformation into its space, and only that part is
+    /// shaded.
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.intersect(ray).map(|distance| Intersection::new(distance, self.as_drawable()))
+    }
+    /// Intersection with the surface `distance` along the ray, which is known to be an end of one
+    /// of the `intervals`.
+    fn hit_at(&self, _ray: &Ray, distance: f64) -> Intersection<'_> {
+        Intersection::new(distance, self.as_drawable())
+    }
+}
+
+/// Upcast to a trait object, implemented for every `Drawable`.
+pub trait AsDrawable {
+    fn as_drawable(&self) -> &dyn Drawable;
+}
+
+impl<T: Drawable> AsDrawable for T {
+    fn as_drawable(&self) -> &dyn Drawable {
+        self
+    }
 }
 
 pub trait Colorable {
     fn get_color(&self) -> &Color;
 }
 
+/// Linear RGB color. Components are not limited to `[0, 1]`, light can be arbitrarily bright.
 #[derive(PartialEq, Debug, Clone)]
 pub struct Color {
-    red: u8,
-    green: u8,
-    blue: u8,
+    red: f64,
+    green: f64,
+    blue: f64,
 }
 
 impl Color {
-    pub fn new(red: u8, green: u8, blue: u8) -> Color {
+    pub fn new(red: f64, green: f64, blue: f64) -> Color {
         Color {
             red: red,
             green: green,
             blue: blue,
         }
     }
-    pub fn get(&self) -> [u8; 3] {
+    pub fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    pub fn from_array(arr: [f64; 3]) -> Color {
+        Color::new(arr[0], arr[1], arr[2])
+    }
+    /// Decodes an 8 bit sRGB color, as stored in most image files, into linear RGB.
+    pub fn from_srgb8(red: u8, green: u8, blue: u8) -> Color {
+        Color::new(srgb_to_linear(red as f64 / 255.0), srgb_to_linear(green as f64 / 255.0),
+                   srgb_to_linear(blue as f64 / 255.0))
+    }
+    pub fn get(&self) -> [f64; 3] {
         [self.red, self.green, self.blue]
     }
+    pub fn plus(&self, color: &Color) -> Color {
+        Color::new(self.red + color.red, self.green + color.green, self.blue + color.blue)
+    }
+    /// Component-wise product, e.g. light of one color reflected off a surface of another.
+    pub fn times(&self, color: &Color) -> Color {
+        Color::new(self.red * color.red, self.green * color.green, self.blue * color.blue)
+    }
+    pub fn factor(&self, factor: f64) -> Color {
+        Color::new(self.red * factor, self.green * factor, self.blue * factor)
+    }
+    /// Relative luminance with Rec. 709 primaries.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+}
+
+impl ops::Add<Color> for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Self::Output {
+        self.plus(&rhs)
+    }
+}
+
+impl ops::Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        self.times(&rhs)
+    }
+}
+
+impl ops::Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        self.factor(rhs)
+    }
+}
+
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Axis-aligned bounding box. Unbounded objects (like planes) report `BoundingBox::infinite()`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BoundingBox {
+    min: Vector,
+    max: Vector,
+}
+
+impl BoundingBox {
+    pub fn new(min: Vector, max: Vector) -> BoundingBox {
+        BoundingBox {
+            min: min,
+            max: max,
+        }
+    }
+    /// Box containing nothing, the identity for `union`.
+    pub fn empty() -> BoundingBox {
+        BoundingBox::new(Vector::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+                         Vector::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY))
+    }
+    pub fn infinite() -> BoundingBox {
+        BoundingBox::new(Vector::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+                         Vector::new(f64::INFINITY, f64::INFINITY, f64::INFINITY))
+    }
+    pub fn from_points(points: &[&Vector]) -> BoundingBox {
+        points.iter().fold(BoundingBox::empty(), |bounds, point| bounds.include(point))
+    }
+    pub fn get_min(&self) -> &Vector {
+        &self.min
+    }
+    pub fn get_max(&self) -> &Vector {
+        &self.max
+    }
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|axis| self.min.get_axis(axis).is_finite() && self.max.get_axis(axis).is_finite())
+    }
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(self.min.min(&other.min), self.max.max(&other.max))
+    }
+    /// Box around the space inside both boxes.
+    pub fn overlap(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(self.min.max(&other.min), self.max.min(&other.max))
+    }
+    pub fn include(&self, point: &Vector) -> BoundingBox {
+        BoundingBox::new(self.min.min(point), self.max.max(point))
+    }
+    /// Distance from `point` to the nearest point of the box, 0 inside of it.
+    pub fn distance(&self, point: &Vector) -> f64 {
+        self.min.minus(point).max(&point.minus(&self.max)).max(&Vector::zero()).euclidian_distance()
+    }
+    pub fn centroid(&self) -> Vector {
+        self.min.plus(&self.max).factor(0.5)
+    }
+    pub fn extent(&self) -> Vector {
+        self.max.minus(&self.min)
+    }
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.extent();
+        if extent.get_x() < 0.0 || extent.get_y() < 0.0 || extent.get_z() < 0.0 {
+            return 0.0;
+        }
+        2.0 * (extent.get_x() * extent.get_y() + extent.get_y() * extent.get_z() + extent.get_z() * extent.get_x())
+    }
+    pub fn largest_axis(&self) -> usize {
+        let extent = self.extent();
+        if extent.get_x() >= extent.get_y() && extent.get_x() >= extent.get_z() {
+            0
+        } else if extent.get_y() >= extent.get_z() {
+            1
+        } else {
+            2
+        }
+    }
+    /// Slab test against a ray given by its origin and per-axis inverse direction. Returns the
+    /// distance at which the ray enters the box if that happens before `max_distance`.
+    pub fn intersect(&self, origin: &Vector, inverse_direction: &Vector, max_distance: f64) -> Option<f64> {
+        self.span(origin, inverse_direction, max_distance).map(|(near, _)| near)
+    }
+    /// Distances between which the ray is inside the box, limited to `[0, max_distance]`.
+    pub fn span(&self, origin: &Vector, inverse_direction: &Vector, max_distance: f64) -> Option<(f64, f64)> {
+        let mut near = 0.0_f64;
+        let mut far = max_distance;
+        for axis in 0..3 {
+            let inverse = inverse_direction.get_axis(axis);
+            let origin = origin.get_axis(axis);
+            let mut t0 = (self.min.get_axis(axis) - origin) * inverse;
+            let mut t1 = (self.max.get_axis(axis) - origin) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // written so that NaNs (0 * inf) keep the previous bounds
+            near = if t0 > near { t0 } else { near };
+            far = if t1 < far { t1 } else { far };
+            if near > far {
+                return None;
+            }
+        }
+        Some((near, far))
+    }
 }
 
+#[derive(Debug, Clone)]
 pub struct Ray {
     origin: Vector,
     direction: Vector,
+    /// Moment the ray is sent at, moving objects are intersected where they are at that time.
+    time: f64,
 }
 
 impl Ray {
-    pub fn new(x: u32, y: u32, scene: &Scene) -> Ray {
-        let fov_adjustment = (scene.get_fov().to_radians() / 2.0).tan();
-        let aspect_ratio = (scene.get_width() as f64) / (scene.get_height() as f64);
-        let sensor_x = ((((x as f64 + 0.5) / scene.get_width() as f64) * 2.0 - 1.0) * aspect_ratio) * fov_adjustment;
-        let sensor_y = (1.0 - ((y as f64 + 0.5) / scene.get_height() as f64) * 2.0) * fov_adjustment;
-
-        Ray {
-            origin: Vector::zero(),
-            direction: Vector::new(sensor_x, sensor_y, -1.0).normalize(),
-        }
-    }
     pub fn from(origin: Vector, direction: Vector) -> Ray {
         Ray {
             origin: origin,
             direction: direction,
+            time: 0.0,
         }
     }
     pub fn from_reflection(normal: &Vector, incident: &Vector, intersection: &Vector, bias: f64) -> Ray {
+        Ray::from(intersection.plus(&normal.factor(bias)), incident.minus(&normal.factor(2.0 * incident.dot(normal))))
+    }
+    /// Bends the incident ray through a surface with the given refraction index, using Snell's law.
+    /// The normal points out of the object; whether the ray enters or leaves is decided by the
+    /// incident direction. Returns `None` on total internal reflection.
+    pub fn from_refraction(normal: &Vector, incident: &Vector, intersection: &Vector, bias: f64,
+                           index: f64) -> Option<Ray> {
+        let mut refraction_normal = normal.clone();
+        let mut eta_t = index;
+        let mut eta_i = 1.0;
+        let mut i_dot_n = incident.dot(normal);
+        if i_dot_n < 0.0 {
+            // outside the surface
+            i_dot_n = -i_dot_n;
+        } else {
+            // inside the surface, invert the normal and swap the indices of refraction
+            refraction_normal = normal.neg();
+            eta_i = eta_t;
+            eta_t = 1.0;
+        }
+
+        let eta = eta_i / eta_t;
+        let k = 1.0 - (eta * eta) * (1.0 - i_dot_n * i_dot_n);
+        if k < 0.0 {
+            return None;
+        }
+        Some(Ray::from(
+            intersection.minus(&refraction_normal.factor(bias)),
+            incident.plus(&refraction_normal.factor(i_dot_n)).factor(eta)
+                .minus(&refraction_normal.factor(k.sqrt()))
+                .normalize(),
+        ))
+    }
+    /// The same ray sent at `time`, rays start at time 0.
+    pub fn with_time(self, time: f64) -> Ray {
         Ray {
-            origin: intersection.plus(&normal.factor(bias)),
-            direction: incident.minus(&normal.factor(2.0 * incident.dot(&normal)))
+            time: time,
+            ..self
         }
     }
     pub fn get_origin(&self) -> &Vector {
@@ -80,24 +314,76 @@ impl Ray {
     pub fn get_direction(&self) -> &Vector {
         &self.direction
     }
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
 }
 
 pub struct Intersection<'a> {
     distance: f64,
-    object: &'a Box<dyn Drawable>,
+    object: &'a dyn Drawable,
+    transform: Option<Transform>,
+    inverted: bool,
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(distance: f64, object: &'a Box<dyn Drawable>) -> Intersection {
+    pub fn new(distance: f64, object: &'a dyn Drawable) -> Intersection<'a> {
         Intersection {
             distance: distance,
             object: object,
+            transform: None,
+            inverted: false,
         }
     }
     pub fn get_distance(&self) -> f64 {
         self.distance
     }
-    pub fn get_object(&self) -> &'a Box<dyn Drawable> {
+    /// The primitive that was hit.
+    pub fn get_object(&self) -> &'a dyn Drawable {
         self.object
     }
+    /// Transformation from the space of the object to world space, `None` for objects placed
+    /// directly in the scene.
+    pub fn get_transform(&self) -> Option<&Transform> {
+        self.transform.as_ref()
+    }
+    /// Moves the intersection out of an instance placed by `transform` at `distance`.
+    pub fn transformed(self, distance: f64, transform: &Transform) -> Intersection<'a> {
+        let transform = match self.transform {
+            Some(inner) => inner.then(transform),
+            None => transform.clone(),
+        };
+        Intersection {
+            distance: distance,
+            object: self.object,
+            transform: Some(transform),
+            inverted: self.inverted,
+        }
+    }
+    /// Turns the surface normal around, for surfaces that bound a solid from the outside like the
+    /// hollowed out part of a CSG difference.
+    pub fn inverted(self) -> Intersection<'a> {
+        Intersection {
+            inverted: !self.inverted,
+            ..self
+        }
+    }
+    /// Unit surface normal at a hit point in world space.
+    pub fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let normal = match &self.transform {
+            Some(transform) => {
+                let normal = self.object.surface_normal(&transform.invert_point(hit_point));
+                transform.apply_normal(&normal).normalize()
+            }
+            None => self.object.surface_normal(hit_point),
+        };
+        if self.inverted { normal.neg() } else { normal }
+    }
+    /// Texture color at a hit point in world space.
+    pub fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        match &self.transform {
+            Some(transform) => self.object.get_texture_color(&transform.invert_point(hit_point)),
+            None => self.object.get_texture_color(hit_point),
+        }
+    }
 }
@@ -0,0 +1,277 @@
+//! Bounding volume hierarchy over the objects of a scene, built with the surface area heuristic.
+//!
+//! The hierarchy only stores indices into the object list it was built from, so the same slice
+//! has to be passed to `trace`. Objects with infinite bounds (planes) are kept aside and tested
+//! against every ray.
+
+use crate::base::{BoundingBox, Drawable, Intersection, Ray};
+use crate::vector::Vector;
+
+const BIN_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 48;
+const STACK_SIZE: usize = MAX_DEPTH + 2;
+
+/// Relative cost of traversing a node compared to intersecting a single object.
+const TRAVERSAL_COST: f64 = 0.125;
+
+enum NodeKind {
+    Leaf { first: usize, count: usize },
+    /// The first child immediately follows its parent, the second one is stored explicitly.
+    Interior { second: usize, axis: usize },
+}
+
+struct Node {
+    bounds: BoundingBox,
+    kind: NodeKind,
+}
+
+struct BuildItem {
+    index: usize,
+    bounds: BoundingBox,
+    centroid: Vector,
+}
+
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new<T: AsRef<dyn Drawable>>(objects: &[T]) -> Bvh {
+        let mut items = Vec::with_capacity(objects.len());
+        let mut unbounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            let bounds = object.as_ref().bounding_box();
+            if bounds.is_finite() {
+                items.push(BuildItem {
+                    index: index,
+                    centroid: bounds.centroid(),
+                    bounds: bounds,
+                });
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(2 * items.len()),
+            indices: Vec::with_capacity(items.len()),
+            unbounded: unbounded,
+        };
+        if !items.is_empty() {
+            bvh.build(&mut items, 0);
+        }
+        bvh
+    }
+
+    fn build(&mut self, items: &mut [BuildItem], depth: usize) -> usize {
+        let bounds = items.iter().fold(BoundingBox::empty(), |b, item| b.union(&item.bounds));
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: bounds,
+            kind: NodeKind::Leaf { first: 0, count: 0 },
+        });
+
+        let split = if items.len() <= MAX_LEAF_SIZE || depth >= MAX_DEPTH {
+            None
+        } else {
+            self.find_split(items, &self.nodes[node].bounds)
+        };
+        match split {
+            None => {
+                let first = self.indices.len();
+                self.indices.extend(items.iter().map(|item| item.index));
+                self.nodes[node].kind = NodeKind::Leaf { first: first, count: items.len() };
+            }
+            Some((axis, position)) => {
+                let mid = partition(items, |item| item.centroid.get_axis(axis) < position);
+                let mid = if mid == 0 || mid == items.len() {
+                    // all centroids ended up on one side, fall back to a median split
+                    items.sort_by(|a, b| a.centroid.get_axis(axis)
+                        .partial_cmp(&b.centroid.get_axis(axis))
+                        .unwrap_or(std::cmp::Ordering::Equal));
+                    items.len() / 2
+                } else {
+                    mid
+                };
+                let (left, right) = items.split_at_mut(mid);
+                self.build(left, depth + 1);
+                let second = self.build(right, depth + 1);
+                self.nodes[node].kind = NodeKind::Interior { second: second, axis: axis };
+            }
+        }
+        node
+    }
+
+    /// Finds the cheapest split plane by binning centroids along the widest axis. Returns `None`
+    /// if keeping the items in a single leaf is cheaper.
+    fn find_split(&self, items: &[BuildItem], bounds: &BoundingBox) -> Option<(usize, f64)> {
+        let centroid_bounds = items.iter()
+            .fold(BoundingBox::empty(), |b, item| b.include(&item.centroid));
+        let axis = centroid_bounds.largest_axis();
+        let min = centroid_bounds.get_min().get_axis(axis);
+        let extent = centroid_bounds.extent().get_axis(axis);
+        if extent <= 0.0 {
+            return None;
+        }
+
+        let bin_of = |item: &BuildItem| {
+            (((item.centroid.get_axis(axis) - min) / extent * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+        };
+        let mut counts = [0_usize; BIN_COUNT];
+        let mut bin_bounds: Vec<BoundingBox> = (0..BIN_COUNT).map(|_| BoundingBox::empty()).collect();
+        for item in items {
+            let bin = bin_of(item);
+            counts[bin] += 1;
+            bin_bounds[bin] = bin_bounds[bin].union(&item.bounds);
+        }
+
+        // cost of splitting after bin i, sweeping from both ends
+        let mut right_area = [0.0; BIN_COUNT];
+        let mut right_count = [0; BIN_COUNT];
+        let mut accumulated = BoundingBox::empty();
+        let mut count = 0;
+        for i in (1..BIN_COUNT).rev() {
+            accumulated = accumulated.union(&bin_bounds[i]);
+            count += counts[i];
+            right_area[i - 1] = accumulated.surface_area();
+            right_count[i - 1] = count;
+        }
+
+        let mut best: Option<(usize, f64)> = None;
+        let mut accumulated = BoundingBox::empty();
+        let mut count = 0;
+        for i in 0..BIN_COUNT - 1 {
+            accumulated = accumulated.union(&bin_bounds[i]);
+            count += counts[i];
+            if count == 0 || right_count[i] == 0 {
+                continue;
+            }
+            let cost = accumulated.surface_area() * count as f64 + right_area[i] * right_count[i] as f64;
+            if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                best = Some((i, cost));
+            }
+        }
+
+        let (bin, cost) = best?;
+        let split_cost = TRAVERSAL_COST + cost / bounds.surface_area();
+        if split_cost >= items.len() as f64 && items.len() <= 4 * MAX_LEAF_SIZE {
+            return None;
+        }
+        Some((axis, min + extent * (bin + 1) as f64 / BIN_COUNT as f64))
+    }
+
+    /// Finds the closest intersection with `objects`, which must be the slice the hierarchy was
+    /// built from.
+    pub fn trace<'a, T: AsRef<dyn Drawable>>(&self, ray: &Ray, objects: &'a [T]) -> Option<Intersection<'a>> {
+        let mut closest: Option<Intersection<'a>> = None;
+        let mut closest_distance = f64::INFINITY;
+        let mut test = |index: usize, closest_distance: &mut f64| {
+            let object = objects[index].as_ref();
+            if let Some(intersection) = object.hit(ray) {
+                if intersection.get_distance() < *closest_distance {
+                    *closest_distance = intersection.get_distance();
+                    closest = Some(intersection);
+                }
+            }
+        };
+
+        for &index in self.unbounded.iter() {
+            test(index, &mut closest_distance);
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let direction = ray.get_direction();
+        let inverse_direction = Vector::new(1.0 / direction.get_x(), 1.0 / direction.get_y(),
+                                            1.0 / direction.get_z());
+        let mut stack = [0_usize; STACK_SIZE];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node_index = stack[stack_size];
+            let node = &self.nodes[node_index];
+            if node.bounds.intersect(ray.get_origin(), &inverse_direction, closest_distance).is_none() {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { first, count } => {
+                    for &index in &self.indices[first..first + count] {
+                        test(index, &mut closest_distance);
+                    }
+                }
+                NodeKind::Interior { second, axis } => {
+                    // visit the child nearer to the ray origin first
+                    let (near, far) = if direction.get_axis(axis) < 0.0 {
+                        (second, node_index + 1)
+                    } else {
+                        (node_index + 1, second)
+                    };
+                    stack[stack_size] = far;
+                    stack[stack_size + 1] = near;
+                    stack_size += 2;
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// Moves items matching the predicate to the front and returns how many there are.
+fn partition<T, F: Fn(&T) -> bool>(items: &mut [T], predicate: F) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Color;
+    use crate::material::{Material, SurfaceType};
+    use crate::objects::plane::Plane;
+    use crate::objects::sphere::Sphere;
+
+    fn material() -> Material {
+        Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0)
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        let mut objects: Vec<Box<dyn Drawable>> = Vec::new();
+        for i in 0..500 {
+            let x = ((i * 37) % 100) as f64 - 50.0;
+            let y = ((i * 61) % 100) as f64 - 50.0;
+            let z = -((i * 17) % 100) as f64 - 10.0;
+            objects.push(Box::new(Sphere::new(Vector::new(x, y, z), 1.0 + (i % 3) as f64, material())));
+        }
+        objects.push(Box::new(Plane::new(Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, -60.0, 0.0), material())));
+        let bvh = Bvh::new(&objects);
+
+        for i in 0..200 {
+            let direction = Vector::new((i % 20) as f64 / 10.0 - 1.0, (i / 20) as f64 / 10.0 - 0.5, -1.0);
+            let ray = Ray::from(Vector::zero(), direction.normalize());
+
+            let expected = objects.iter()
+                .filter_map(|o| o.intersect(&ray))
+                .fold(None, |closest: Option<f64>, d| Some(closest.map_or(d, |c| c.min(d))));
+            assert_eq!(bvh.trace(&ray, &objects).map(|i| i.get_distance()), expected);
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let objects: Vec<Box<dyn Drawable>> = Vec::new();
+        let bvh = Bvh::new(&objects);
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        assert!(bvh.trace(&ray, &objects).is_none());
+    }
+}
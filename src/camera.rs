@@ -0,0 +1,344 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+use crate::base::Ray;
+use crate::vector::Vector;
+
+/// How the directions around the camera are laid out on the image.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Projection {
+    /// Straight lines stay straight, `fov` is the vertical field of view.
+    Perspective,
+    /// Parallel rays, showing what the perspective projection shows at the distance of `look_at`.
+    Orthographic,
+    /// Fisheye whose distance from the image center grows with the angle from the viewing
+    /// direction, `fov` is the vertical field of view and may reach 360 degrees. The image is black
+    /// outside the circle the field of view fills.
+    Equidistant,
+    /// Fisheye keeping the areas of the solid angles, `fov` is at most 360 degrees like above.
+    Equisolid,
+    /// All directions, longitude across and latitude down the image, with the viewing direction in
+    /// the middle. `fov` is not used and the image should be twice as wide as high.
+    Equirectangular,
+}
+
+/// Camera placed at `position` and looking at `look_at`. It is a pinhole unless given an aperture,
+/// then it is a thin lens that keeps only the plane at the focal distance sharp. The lens only works
+/// with the perspective and orthographic projections.
+pub struct Camera {
+    position: Vector,
+    look_at: Vector,
+    up: Vector,
+    fov: f64,
+    aspect_ratio: f64,
+    forward: Vector,
+    right: Vector,
+    vertical: Vector,
+    projection: Projection,
+    /// Radius of the lens.
+    aperture: f64,
+    /// Distance from the lens to the plane in focus, along the viewing direction.
+    focal_distance: f64,
+    /// Number of diaphragm blades shaping the out of focus highlights, 0 for a round opening.
+    blades: u32,
+    /// Times between which the rays are sent, moving objects are blurred along their way.
+    shutter_open: f64,
+    shutter_close: f64,
+}
+
+impl Camera {
+    /// Creates a camera with a vertical field of view of `fov` degrees. `up` only has to be roughly
+    /// perpendicular to the viewing direction, it is orthogonalised here.
+    pub fn new(position: Vector, look_at: Vector, up: Vector, fov: f64, aspect_ratio: f64) -> Camera {
+        let mut camera = Camera {
+            position: position,
+            look_at: look_at,
+            up: up,
+            fov: fov,
+            aspect_ratio: aspect_ratio,
+            forward: Vector::zero(),
+            right: Vector::zero(),
+            vertical: Vector::zero(),
+            projection: Projection::Perspective,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            blades: 0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        };
+        camera.focal_distance = camera.look_at.minus(&camera.position).euclidian_distance();
+        camera.update_basis();
+        camera
+    }
+    fn update_basis(&mut self) {
+        self.forward = self.look_at.minus(&self.position).normalize();
+        self.right = self.forward.cross(&self.up).normalize();
+        self.vertical = self.right.cross(&self.forward);
+    }
+    /// Generates the ray through a point of the image plane, where `(0, 0)` is the top left and
+    /// `(1, 1)` the bottom right corner of the image. `None` outside the image circle of a fisheye,
+    /// where the point lies beyond its field of view.
+    pub fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let half_fov = self.fov.to_radians() / 2.0;
+        // position on the image with y from -1 to 1 whatever the shape, x spanning the aspect ratio and up being
+        // positive, so `fov` is the vertical field of view also of portrait images
+        let x = (s * 2.0 - 1.0) * self.aspect_ratio;
+        let y = 1.0 - t * 2.0;
+        match self.projection {
+            Projection::Perspective => {
+                let fov_adjustment = half_fov.tan();
+                let direction = self.forward
+                    .plus(&self.right.factor(x * fov_adjustment))
+                    .plus(&self.vertical.factor(y * fov_adjustment));
+                Some(Ray::from(self.position.clone(), direction.normalize()))
+            }
+            Projection::Orthographic => {
+                let half_height = self.look_at.minus(&self.position).euclidian_distance() * half_fov.tan();
+                let origin = self.position
+                    .plus(&self.right.factor(x * half_height))
+                    .plus(&self.vertical.factor(y * half_height));
+                Some(Ray::from(origin, self.forward.clone()))
+            }
+            Projection::Equidistant | Projection::Equisolid => {
+                let radius = (x * x + y * y).sqrt();
+                let theta = if self.projection == Projection::Equidistant {
+                    radius * half_fov
+                } else {
+                    let sine = radius * (half_fov / 2.0).sin();
+                    if sine > 1.0 {
+                        return None;
+                    }
+                    2.0 * sine.asin()
+                };
+                if theta > PI {
+                    return None;
+                }
+                let (sin_phi, cos_phi) = if radius > 0.0 { (y / radius, x / radius) } else { (0.0, 0.0) };
+                let direction = self.forward.factor(theta.cos())
+                    .plus(&self.right.factor(theta.sin() * cos_phi))
+                    .plus(&self.vertical.factor(theta.sin() * sin_phi));
+                Some(Ray::from(self.position.clone(), direction.normalize()))
+            }
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - t) * PI;
+                let direction = self.forward.factor(latitude.cos() * longitude.cos())
+                    .plus(&self.right.factor(latitude.cos() * longitude.sin()))
+                    .plus(&self.vertical.factor(latitude.sin()));
+                Some(Ray::from(self.position.clone(), direction.normalize()))
+            }
+        }
+    }
+    /// Generates the ray through a point of the image plane like `get_ray`, starting from the point
+    /// of the lens corresponding to `(u, v)` in `[0, 1)²`. All the rays through the same point of
+    /// the image meet on the plane in focus.
+    pub fn get_lens_ray(&self, s: f64, t: f64, u: f64, v: f64) -> Option<Ray> {
+        let ray = self.get_ray(s, t)?;
+        let has_lens = match self.projection {
+            Projection::Perspective | Projection::Orthographic => self.aperture > 0.0,
+            _ => false,
+        };
+        if !has_lens {
+            return Some(ray);
+        }
+        let direction = ray.get_direction();
+        let focus = ray.get_origin().plus(&direction.factor(self.focal_distance / direction.dot(&self.forward)));
+        let (x, y) = self.sample_lens(u, v);
+        let origin = ray.get_origin().plus(&self.right.factor(x)).plus(&self.vertical.factor(y));
+        let direction = focus.minus(&origin).normalize();
+        Some(Ray::from(origin, direction))
+    }
+    /// Point on the opening of the lens, a disk or a regular polygon with a corner at the top.
+    fn sample_lens(&self, u: f64, v: f64) -> (f64, f64) {
+        if self.blades < 3 {
+            // the square root spreads the points uniformly over the area
+            let r = self.aperture * u.sqrt();
+            let phi = 2.0 * PI * v;
+            return (r * phi.cos(), r * phi.sin());
+        }
+        // `u` picks one of the triangles between the center and the edges, and is then stretched
+        // back over it
+        let sides = self.blades as f64;
+        let side = (u * sides).floor().min(sides - 1.0);
+        let u = u * sides - side;
+        let corner = |i: f64| {
+            let angle = PI / 2.0 + 2.0 * PI * i / sides;
+            (self.aperture * angle.cos(), self.aperture * angle.sin())
+        };
+        let (a, b) = (corner(side), corner(side + 1.0));
+        // folding the unit square onto its lower triangle keeps the points uniform
+        let (u, v) = if u + v > 1.0 { (1.0 - u, 1.0 - v) } else { (u, v) };
+        (a.0 * u + b.0 * v, a.1 * u + b.1 * v)
+    }
+    /// Makes the camera a thin lens of radius `aperture`, 0 keeps it a pinhole.
+    pub fn set_aperture(&mut self, aperture: f64) {
+        self.aperture = aperture;
+    }
+    /// Sets how far in front of the camera the scene is sharp, by default at `look_at`.
+    pub fn set_focal_distance(&mut self, focal_distance: f64) {
+        self.focal_distance = focal_distance;
+    }
+    /// Gives the lens opening the shape of a regular polygon with `blades` corners, fewer than three
+    /// keep it round.
+    pub fn set_blades(&mut self, blades: u32) {
+        self.blades = blades;
+    }
+    /// Sends the rays at times between `open` and `close`, by default all at time 0.
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open;
+        self.shutter_close = close;
+    }
+    pub fn get_shutter(&self) -> (f64, f64) {
+        (self.shutter_open, self.shutter_close)
+    }
+    /// Whether the rays are sent at different times.
+    pub fn has_motion_blur(&self) -> bool {
+        self.shutter_close > self.shutter_open
+    }
+    /// Time of a ray for `u` in `[0, 1)`, spread evenly while the shutter is open.
+    pub fn shutter_time(&self, u: f64) -> f64 {
+        self.shutter_open + (self.shutter_close - self.shutter_open) * u
+    }
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+    pub fn get_projection(&self) -> Projection {
+        self.projection
+    }
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) {
+        self.aspect_ratio = aspect_ratio;
+    }
+    pub fn get_position(&self) -> &Vector {
+        &self.position
+    }
+    pub fn get_look_at(&self) -> &Vector {
+        &self.look_at
+    }
+    pub fn get_up(&self) -> &Vector {
+        &self.up
+    }
+    pub fn get_fov(&self) -> f64 {
+        self.fov
+    }
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+    pub fn get_aperture(&self) -> f64 {
+        self.aperture
+    }
+    pub fn get_focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+    pub fn get_blades(&self) -> u32 {
+        self.blades
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_center_ray_hits_look_at() {
+        let camera = Camera::new(Vector::new(1.0, 2.0, 3.0), Vector::new(4.0, 2.0, -1.0),
+                                 Vector::new(0.0, 1.0, 0.0), 60.0, 1.5);
+        let ray = camera.get_ray(0.5, 0.5).unwrap();
+
+        assert_eq!(ray.get_origin(), &Vector::new(1.0, 2.0, 3.0));
+        assert_eq!(ray.get_direction(), &Vector::new(0.6, 0.0, -0.8));
+    }
+
+    #[test]
+    fn test_corners() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 90.0, 2.0);
+        let top_left = camera.get_ray(0.0, 0.0).unwrap();
+        let direction = top_left.get_direction();
+
+        assert!(direction.get_x() < 0.0 && direction.get_y() > 0.0);
+        assert!((direction.get_x() / direction.get_z() - 2.0).abs() < 1e-9);
+        assert!((direction.get_y() / direction.get_z() + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lens_rays_meet_in_focus() {
+        let mut camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -4.0), Vector::new(0.0, 1.0, 0.0),
+                                     60.0, 1.5);
+        camera.set_aperture(0.5);
+        let pinhole = camera.get_ray(0.2, 0.7).unwrap();
+        let in_focus = pinhole.get_direction().factor(4.0 / -pinhole.get_direction().get_z());
+
+        for &blades in [0, 3, 6].iter() {
+            camera.set_blades(blades);
+            for &(u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.3), (0.0, 0.99)].iter() {
+                let ray = camera.get_lens_ray(0.2, 0.7, u, v).unwrap();
+                let origin = ray.get_origin();
+                assert!(origin.get_z() == 0.0 && origin.euclidian_distance() <= 0.5 + 1e-12);
+                let distance = 4.0 / -ray.get_direction().get_z();
+                let focus = origin.plus(&ray.get_direction().factor(distance));
+                assert!(focus.minus(&in_focus).euclidian_distance() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_polygonal_lens() {
+        let mut camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                     60.0, 1.0);
+        camera.set_aperture(1.0);
+        camera.set_blades(4);
+        // a square standing on a corner, |x| + |y| stays within the radius
+        for i in 0..100 {
+            let (x, y) = camera.sample_lens(i as f64 / 100.0, (i * 37 % 100) as f64 / 100.0);
+            assert!(x.abs() + y.abs() <= 1.0 + 1e-12);
+        }
+    }
+
+    fn direction(camera: &Camera, s: f64, t: f64) -> Vector {
+        camera.get_ray(s, t).unwrap().get_direction().clone()
+    }
+
+    #[test]
+    fn test_projections() {
+        let mut camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -2.0), Vector::new(0.0, 1.0, 0.0),
+                                     90.0, 2.0);
+        let close = |a: &Vector, b: &Vector| a.minus(b).euclidian_distance() < 1e-9;
+
+        // the view of the perspective projection at the distance of `look_at`
+        camera.set_projection(Projection::Orthographic);
+        let top_left = camera.get_ray(0.0, 0.0).unwrap();
+        assert!(close(top_left.get_origin(), &Vector::new(-4.0, 2.0, 0.0)));
+        assert_eq!(top_left.get_direction(), &Vector::new(0.0, 0.0, -1.0));
+
+        // half way up the image is half of the vertical field of view
+        camera.set_projection(Projection::Equidistant);
+        assert!(close(&direction(&camera, 0.5, 0.5), &Vector::new(0.0, 0.0, -1.0)));
+        let angle = 22.5_f64.to_radians();
+        assert!(close(&direction(&camera, 0.5, 0.25), &Vector::new(0.0, angle.sin(), -angle.cos())));
+        camera.set_projection(Projection::Equisolid);
+        assert!(close(&direction(&camera, 0.5, 0.0), &Vector::new(0.0, 0.5_f64.sqrt(), -0.5_f64.sqrt())));
+
+        camera.set_projection(Projection::Equirectangular);
+        assert!(close(&direction(&camera, 0.5, 0.5), &Vector::new(0.0, 0.0, -1.0)));
+        assert!(close(&direction(&camera, 0.75, 0.5), &Vector::new(1.0, 0.0, 0.0)));
+        assert!(close(&direction(&camera, 0.0, 0.5), &Vector::new(0.0, 0.0, 1.0)));
+        assert!(close(&direction(&camera, 0.3, 0.0), &Vector::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn test_image_circle() {
+        let mut camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                     360.0, 2.0);
+        for &projection in [Projection::Equidistant, Projection::Equisolid].iter() {
+            camera.set_projection(projection);
+            // the corners of a wide image lie beyond the field of view
+            assert!(camera.get_ray(0.0, 0.0).is_none());
+            assert!(camera.get_lens_ray(1.0, 1.0, 0.5, 0.5).is_none());
+            // the edge of the image circle looks straight back
+            let back = direction(&camera, 0.5, 0.0);
+            assert!(back.minus(&Vector::new(0.0, 0.0, 1.0)).euclidian_distance() < 1e-6);
+            assert!(camera.get_ray(0.2, 0.5).is_none());
+        }
+    }
+}
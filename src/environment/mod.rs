@@ -0,0 +1,167 @@
+//! What rays see when they leave the scene without hitting anything.
+
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use image::hdr::HDRDecoder;
+use image::ImageResult;
+
+use crate::base::Color;
+use crate::vector::Vector;
+
+pub mod sky;
+
+pub trait Environment: Send + Sync {
+    /// Radiance arriving from infinitely far away against the unit `direction`, which points away
+    /// from the scene.
+    fn radiance(&self, direction: &Vector) -> Color;
+    /// Columns and rows of the latitude-longitude grid an `EnvironmentLight` picks its samples
+    /// from, environments with more detail need a finer one.
+    fn sampling_resolution(&self) -> (usize, usize) {
+        (64, 32)
+    }
+}
+
+/// Coordinates in `[0, 1]²` of the direction in an equirectangular image, with straight up along
+/// the top edge and `-z` in the middle.
+pub fn to_lat_long(direction: &Vector) -> (f64, f64) {
+    let u = 0.5 + direction.get_x().atan2(-direction.get_z()) / (2.0 * PI);
+    let v = direction.get_y().clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+/// Unit direction at the coordinates of an equirectangular image, the inverse of `to_lat_long`.
+pub fn from_lat_long(u: f64, v: f64) -> Vector {
+    let phi = (u - 0.5) * 2.0 * PI;
+    let theta = v * PI;
+    Vector::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+}
+
+/// The same color in every direction.
+pub struct SolidEnvironment {
+    color: Color,
+}
+
+impl SolidEnvironment {
+    pub fn new(color: Color) -> SolidEnvironment {
+        SolidEnvironment {
+            color: color,
+        }
+    }
+}
+
+impl Environment for SolidEnvironment {
+    fn radiance(&self, _direction: &Vector) -> Color {
+        self.color.clone()
+    }
+}
+
+/// Blends from the `bottom` color straight down to the `top` color straight up.
+pub struct GradientEnvironment {
+    bottom: Color,
+    top: Color,
+}
+
+impl GradientEnvironment {
+    pub fn new(bottom: Color, top: Color) -> GradientEnvironment {
+        GradientEnvironment {
+            bottom: bottom,
+            top: top,
+        }
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn radiance(&self, direction: &Vector) -> Color {
+        let t = (direction.get_y().clamp(-1.0, 1.0) + 1.0) / 2.0;
+        self.bottom.factor(1.0 - t) + self.top.factor(t)
+    }
+}
+
+/// Equirectangular panorama of linear colors around the scene, usually an HDR photograph.
+pub struct ImageEnvironment {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    strength: f64,
+    rotation: f64,
+}
+
+impl ImageEnvironment {
+    /// `pixels` are in row-major order starting in the top left corner.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> ImageEnvironment {
+        assert_eq!(pixels.len(), width * height, "the pixels have to fill the image");
+        ImageEnvironment {
+            width: width,
+            height: height,
+            pixels: pixels,
+            strength: 1.0,
+            rotation: 0.0,
+        }
+    }
+    /// Reads a Radiance `.hdr` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> ImageResult<ImageEnvironment> {
+        let decoder = HDRDecoder::new(BufReader::new(File::open(path)?))?;
+        let metadata = decoder.metadata();
+        let pixels = decoder.read_image_hdr()?.into_iter()
+            .map(|pixel| Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64))
+            .collect();
+        Ok(ImageEnvironment::new(metadata.width as usize, metadata.height as usize, pixels))
+    }
+    /// Scales the brightness of the image, 1 by default.
+    pub fn set_strength(&mut self, strength: f64) {
+        self.strength = strength;
+    }
+    /// Turns the panorama counterclockwise around the vertical axis, seen from above.
+    pub fn set_rotation(&mut self, degrees: f64) {
+        self.rotation = degrees.to_radians();
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn radiance(&self, direction: &Vector) -> Color {
+        let (u, v) = to_lat_long(direction);
+        let u = u + self.rotation / (2.0 * PI);
+        let x = ((u.rem_euclid(1.0) * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x].factor(self.strength)
+    }
+
+    /// A cell for every pixel, so that single bright pixels like the sun are found.
+    fn sampling_resolution(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lat_long_round_trip() {
+        for &(u, v) in [(0.5, 0.5), (0.1, 0.3), (0.9, 0.8), (0.25, 0.05)].iter() {
+            let (u2, v2) = to_lat_long(&from_lat_long(u, v));
+            assert!((u - u2).abs() < 1e-12 && (v - v2).abs() < 1e-12);
+        }
+        assert_eq!(to_lat_long(&Vector::new(0.0, 0.0, -1.0)), (0.5, 0.5));
+        assert_eq!(to_lat_long(&Vector::new(0.0, 1.0, 0.0)).1, 0.0);
+    }
+
+    #[test]
+    fn test_image_lookup() {
+        // left half red, right half blue
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let mut image = ImageEnvironment::new(2, 1, vec![red.clone(), blue.clone()]);
+        let left = Vector::new(-1.0, 0.0, -0.1).normalize();
+        assert_eq!(image.radiance(&left), red);
+        assert_eq!(image.radiance(&Vector::new(1.0, 0.0, -0.1).normalize()), blue);
+
+        // a quarter turn moves the blue half from the right to the front
+        image.set_rotation(90.0);
+        image.set_strength(2.0);
+        assert_eq!(image.radiance(&left), blue.factor(2.0));
+    }
+}
@@ -0,0 +1,190 @@
+//! Daylight sky after Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight".
+
+use std::f64::consts::PI;
+
+use crate::base::Color;
+use crate::environment::Environment;
+use crate::vector::Vector;
+
+/// Coefficients of the Perez formula for the distribution of luminance or of a chromaticity
+/// coordinate over the sky, linear in the turbidity.
+const LUMINANCE_COEFFICIENTS: [(f64, f64); 5] =
+    [(0.1787, -1.4630), (-0.3554, 0.4275), (-0.0227, 5.3251), (0.1206, -2.5771), (-0.0670, 0.3703)];
+const X_COEFFICIENTS: [(f64, f64); 5] =
+    [(-0.0193, -0.2592), (-0.0665, 0.0008), (-0.0004, 0.2125), (-0.0641, -0.8989), (-0.0033, 0.0452)];
+const Y_COEFFICIENTS: [(f64, f64); 5] =
+    [(-0.0167, -0.2608), (-0.0950, 0.0092), (-0.0079, 0.2102), (-0.0441, -1.6537), (-0.0109, 0.0529)];
+
+/// Fits of the zenith chromaticity, rows for the squared turbidity, the turbidity and the constant
+/// term, columns for the cubed, squared and plain zenith angle of the sun and the constant term.
+const ZENITH_X: [[f64; 4]; 3] = [
+    [0.00166, -0.00375, 0.00209, 0.0],
+    [-0.02903, 0.06377, -0.03202, 0.00394],
+    [0.11693, -0.21196, 0.06052, 0.25886],
+];
+const ZENITH_Y: [[f64; 4]; 3] = [
+    [0.00275, -0.00610, 0.00317, 0.0],
+    [-0.04214, 0.08970, -0.04153, 0.00516],
+    [0.15346, -0.26756, 0.06670, 0.26688],
+];
+
+/// Wavelengths in micrometres standing in for the red, green and blue parts of the spectrum.
+const WAVELENGTHS: [f64; 3] = [0.680, 0.550, 0.440];
+/// Illuminance of the sun above the atmosphere in the units of the sky, kilolux.
+const SUN_ILLUMINANCE: f64 = 128.0;
+/// Lowest point of the sky that is evaluated, the model breaks down below the horizon.
+const MIN_COS_THETA: f64 = 0.01;
+
+/// Perez formula for the relative brightness at zenith angle `theta` and angle `gamma` from the sun.
+fn perez(coefficients: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// One component of the sky, its value at the zenith and how it changes towards other directions.
+struct Distribution {
+    zenith: f64,
+    coefficients: [f64; 5],
+    /// Perez formula at the zenith, which the others are relative to.
+    zenith_perez: f64,
+}
+
+impl Distribution {
+    fn new(zenith: f64, fit: &[(f64, f64); 5], turbidity: f64, sun_theta: f64) -> Distribution {
+        let mut coefficients = [0.0; 5];
+        for (coefficient, &(slope, offset)) in coefficients.iter_mut().zip(fit.iter()) {
+            *coefficient = slope * turbidity + offset;
+        }
+        Distribution {
+            zenith: zenith,
+            coefficients: coefficients,
+            zenith_perez: perez(&coefficients, 1.0, sun_theta),
+        }
+    }
+    fn at(&self, cos_theta: f64, gamma: f64) -> f64 {
+        self.zenith * perez(&self.coefficients, cos_theta, gamma) / self.zenith_perez
+    }
+}
+
+fn zenith_chromaticity(fit: &[[f64; 4]; 3], turbidity: f64, sun_theta: f64) -> f64 {
+    let angles = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+    let row = |row: &[f64; 4]| row.iter().zip(angles.iter()).map(|(a, b)| a * b).sum::<f64>();
+    turbidity * turbidity * row(&fit[0]) + turbidity * row(&fit[1]) + row(&fit[2])
+}
+
+/// Converts CIE xyY to linear sRGB, leaving out colors outside of its gamut.
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
+
+/// Clear sky lit by the sun, with radiance in kilocandela per square metre. Turbidity is the haze
+/// of the air, 2 for a very clear day and 10 for a hazy one. Below the horizon it continues the
+/// color of the horizon, scenes usually have a ground there.
+pub struct SkyEnvironment {
+    sun_direction: Vector,
+    turbidity: f64,
+    luminance: Distribution,
+    x: Distribution,
+    y: Distribution,
+    strength: f64,
+}
+
+impl SkyEnvironment {
+    /// The sun is `elevation` degrees above the horizon, `azimuth` degrees clockwise from `-z` as
+    /// seen from above, so 90 is towards `+x`.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> SkyEnvironment {
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vector::new(elevation.cos() * azimuth.sin(), elevation.sin(),
+                                        -elevation.cos() * azimuth.cos());
+        let sun_theta = (PI / 2.0 - elevation).clamp(0.0, PI / 2.0);
+
+        let chi = (4.0 / 9.0 - turbidity / 120.0) * (PI - 2.0 * sun_theta);
+        let zenith_luminance = ((4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192).max(0.0);
+        SkyEnvironment {
+            sun_direction: sun_direction,
+            turbidity: turbidity,
+            luminance: Distribution::new(zenith_luminance, &LUMINANCE_COEFFICIENTS, turbidity, sun_theta),
+            x: Distribution::new(zenith_chromaticity(&ZENITH_X, turbidity, sun_theta), &X_COEFFICIENTS,
+                                 turbidity, sun_theta),
+            y: Distribution::new(zenith_chromaticity(&ZENITH_Y, turbidity, sun_theta), &Y_COEFFICIENTS,
+                                 turbidity, sun_theta),
+            strength: 1.0,
+        }
+    }
+    /// Scales the brightness of the sky and the sun, 1 by default.
+    pub fn set_strength(&mut self, strength: f64) {
+        self.strength = strength;
+    }
+    /// Unit vector pointing towards the sun.
+    pub fn get_sun_direction(&self) -> &Vector {
+        &self.sun_direction
+    }
+    /// Share of the sunlight of each color that makes it through the atmosphere, from the
+    /// scattering by air molecules and by the haze.
+    pub fn get_sun_color(&self) -> Color {
+        let elevation = self.sun_direction.get_y().clamp(-1.0, 1.0).asin();
+        if elevation <= 0.0 {
+            return Color::black();
+        }
+        // relative length of the way through the air, 1 with the sun straight up
+        let zenith_degrees = 90.0 - elevation.to_degrees();
+        let air_mass = 1.0 / (elevation.sin() + 0.15 * (93.885 - zenith_degrees).powf(-1.253));
+        let haze = 0.04608 * self.turbidity - 0.04586;
+        let [red, green, blue] = WAVELENGTHS.map(|wavelength: f64| {
+            let rayleigh = (-0.008735 * wavelength.powf(-4.08) * air_mass).exp();
+            let aerosol = (-haze * wavelength.powf(-1.3) * air_mass).exp();
+            rayleigh * aerosol
+        });
+        Color::new(red, green, blue)
+    }
+    /// Sunlight falling on a surface facing the sun in the units of the lights, the illuminance
+    /// over π; `get_sun_color` reduces it further.
+    pub fn get_sun_intensity(&self) -> f64 {
+        SUN_ILLUMINANCE / PI * self.strength
+    }
+}
+
+impl Environment for SkyEnvironment {
+    fn radiance(&self, direction: &Vector) -> Color {
+        let cos_theta = direction.get_y().max(MIN_COS_THETA);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let luminance = self.luminance.at(cos_theta, gamma);
+        xyy_to_rgb(self.x.at(cos_theta, gamma), self.y.at(cos_theta, gamma), luminance).factor(self.strength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sky() {
+        let sky = SkyEnvironment::new(40.0, 90.0, 3.0);
+        assert!((sky.get_sun_direction().get_x() - 40.0_f64.to_radians().cos()).abs() < 1e-12);
+
+        let zenith = sky.radiance(&Vector::new(0.0, 1.0, 0.0));
+        let [r, g, b] = zenith.get();
+        // blue sky of a few thousand candela per square metre
+        assert!(b > g && g > r, "{:?}", zenith);
+        assert!(zenith.luminance() > 1.0 && zenith.luminance() < 20.0, "{:?}", zenith);
+        // brighter around the sun than opposite of it
+        let near_sun = sky.radiance(&Vector::new(1.0, 1.0, 0.0).normalize());
+        let away = sky.radiance(&Vector::new(-1.0, 1.0, 0.0).normalize());
+        assert!(near_sun.luminance() > 2.0 * away.luminance());
+    }
+
+    #[test]
+    fn test_sunset_is_red() {
+        let noon = SkyEnvironment::new(60.0, 0.0, 3.0).get_sun_color().get();
+        let sunset = SkyEnvironment::new(3.0, 0.0, 3.0).get_sun_color().get();
+        assert!(noon[0] < 1.0 && noon[2] < noon[0]);
+        assert!(sunset[2] / sunset[0] < 0.5 * noon[2] / noon[0]);
+        assert_eq!(SkyEnvironment::new(-5.0, 0.0, 3.0).get_sun_color(), Color::black());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use image::hdr::HDREncoder;
+use image::{DynamicImage, GenericImage, Rgb, Rgba};
+use serde::Deserialize;
+
+use crate::base::{linear_to_srgb, Color};
+
+/// Curve compressing unbounded scene radiance into the displayable `[0, 1]` range.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneMapping {
+    /// Cuts off everything brighter than 1.
+    Clamp,
+    /// `c / (1 + c)`, never saturates but desaturates highlights.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Filmic,
+}
+
+impl ToneMapping {
+    pub fn apply(&self, value: f64) -> f64 {
+        let value = value.max(0.0);
+        let mapped = match self {
+            ToneMapping::Clamp => value,
+            ToneMapping::Reinhard => value / (1.0 + value),
+            ToneMapping::Filmic => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (value * (a * value + b)) / (value * (c * value + d) + e)
+            }
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// Image of linear, unclamped colors as produced by the renderer.
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width: width,
+            height: height,
+            pixels: vec![Color::black(); (width * height) as usize],
+        }
+    }
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+    pub fn get_pixel(&self, x: u32, y: u32) -> &Color {
+        &self.pixels[(y * self.width + x) as usize]
+    }
+    pub fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+    /// Scales the colors by `2^exposure`, maps them to `[0, 1]` and encodes them as sRGB.
+    pub fn to_image(&self, tone_mapping: ToneMapping, exposure: f64) -> DynamicImage {
+        let scale = 2.0_f64.powf(exposure);
+        let encode = |value: f64| (linear_to_srgb(tone_mapping.apply(value * scale)) * 255.0).round() as u8;
+
+        let mut image = DynamicImage::new_rgba8(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [r, g, b] = self.get_pixel(x, y).get();
+                image.put_pixel(x, y, Rgba([encode(r), encode(g), encode(b), 255]));
+            }
+        }
+        image
+    }
+    /// Writes the unmodified linear colors as a Radiance `.hdr` file.
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let data: Vec<Rgb<f32>> = self.pixels.iter()
+            .map(|color| {
+                let [r, g, b] = color.get();
+                Rgb([r.max(0.0) as f32, g.max(0.0) as f32, b.max(0.0) as f32])
+            })
+            .collect();
+        let writer = BufWriter::new(File::create(path)?);
+        HDREncoder::new(writer).encode(&data, self.width as usize, self.height as usize)
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_mapping_range() {
+        for tone_mapping in [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Filmic].iter() {
+            assert_eq!(tone_mapping.apply(0.0), 0.0);
+            assert_eq!(tone_mapping.apply(-1.0), 0.0);
+            assert!(tone_mapping.apply(1000.0) <= 1.0);
+            assert!(tone_mapping.apply(0.5) < tone_mapping.apply(0.6));
+        }
+        assert_eq!(ToneMapping::Reinhard.apply(1.0), 0.5);
+    }
+
+    #[test]
+    fn test_srgb_encoding() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.put_pixel(0, 0, Color::new(0.5, 1.0, 0.0));
+        framebuffer.put_pixel(1, 0, Color::new(0.25, 2.0, 0.0));
+        let image = framebuffer.to_image(ToneMapping::Clamp, 0.0).raw_pixels();
+        assert_eq!(image[0..3], [188, 255, 0]);
+        // one stop less exposure halves the radiance
+        let darker = framebuffer.to_image(ToneMapping::Clamp, -1.0).raw_pixels();
+        assert_eq!(darker[4..7], [99, 255, 0]);
+    }
+
+    #[test]
+    fn test_srgb_round_trip() {
+        let color = Color::from_srgb8(128, 0, 255);
+        let image = {
+            let mut framebuffer = Framebuffer::new(1, 1);
+            framebuffer.put_pixel(0, 0, color);
+            framebuffer.to_image(ToneMapping::Clamp, 0.0).raw_pixels()
+        };
+        assert_eq!(image[0..3], [128, 0, 255]);
+    }
+}
@@ -0,0 +1,55 @@
+//! Strategies computing the light travelling along camera rays.
+
+use crate::base::{Color, Ray};
+use crate::sampling::Rng;
+use crate::scene::Scene;
+use crate::vector::Vector;
+
+pub mod path;
+pub mod whitted;
+
+pub trait Integrator: Send + Sync {
+    /// Light arriving at the ray origin from the direction of the ray. `rng` belongs to the pixel
+    /// being rendered, so the result is reproducible.
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut Rng) -> Color;
+}
+
+/// Fresnel equations for unpolarised light, giving the fraction of light that is reflected.
+pub fn fresnel(incident: &Vector, normal: &Vector, index: f64) -> f64 {
+    let i_dot_n = incident.dot(normal);
+    let mut eta_i = 1.0;
+    let mut eta_t = index;
+    if i_dot_n > 0.0 {
+        eta_i = eta_t;
+        eta_t = 1.0;
+    }
+
+    let sin_t = eta_i / eta_t * (1.0 - i_dot_n * i_dot_n).max(0.0).sqrt();
+    if sin_t > 1.0 {
+        // total internal reflection
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin_t * sin_t).max(0.0).sqrt();
+    let cos_i = i_dot_n.abs();
+    let r_s = ((eta_t * cos_i) - (eta_i * cos_t)) / ((eta_t * cos_i) + (eta_i * cos_t));
+    let r_p = ((eta_i * cos_i) - (eta_t * cos_t)) / ((eta_i * cos_i) + (eta_t * cos_t));
+    (r_s * r_s + r_p * r_p) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SHADOW_BIAS;
+
+    #[test]
+    fn test_fresnel() {
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let head_on = fresnel(&Vector::new(0.0, -1.0, 0.0), &normal, 1.5);
+        assert!((head_on - 0.04).abs() < 1e-9);
+
+        // leaving glass at a grazing angle is totally reflected
+        let grazing = Vector::new(1.0, 0.2, 0.0).normalize();
+        assert_eq!(fresnel(&grazing, &normal, 1.5), 1.0);
+        assert!(Ray::from_refraction(&normal, &grazing, &Vector::zero(), SHADOW_BIAS, 1.5).is_none());
+    }
+}
@@ -0,0 +1,210 @@
+use std::f64::consts::PI;
+
+use crate::base::{Color, Ray};
+use crate::integrator::{fresnel, Integrator};
+use crate::material::SurfaceType;
+use crate::sampling::{cosine_hemisphere, Rng};
+use crate::scene::{Scene, SHADOW_BIAS};
+
+/// Offset of bounced rays from the surface. Diffuse bounces leave at any angle, so it has to be
+/// larger than the bias of mirrored rays to keep them from hitting the surface they start on.
+const BOUNCE_BIAS: f64 = 1e-9;
+
+/// Unbiased Monte Carlo path tracer. Every bounce gathers the scene lights with shadow rays, like
+/// `WhittedIntegrator` does, and continues in a single randomly chosen direction, picking up the
+/// light of emissive surfaces and the background it runs into.
+pub struct PathTracer {
+    max_depth: u32,
+    roulette_depth: u32,
+}
+
+impl PathTracer {
+    /// Paths are cut randomly with Russian roulette after `roulette_depth` bounces, and always
+    /// after `max_depth` bounces.
+    pub fn new(max_depth: u32, roulette_depth: u32) -> PathTracer {
+        PathTracer {
+            max_depth: max_depth,
+            roulette_depth: roulette_depth,
+        }
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> PathTracer {
+        PathTracer::new(64, 3)
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut Rng) -> Color {
+        let mut radiance = Color::black();
+        // fraction of the light found along the path that reaches the camera
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = ray.clone();
+        // whether the previous bounce sampled the lights, glowing objects among them must not be
+        // counted again when the bounce runs into them
+        let mut lights_sampled = false;
+
+        for depth in 0..self.max_depth {
+            let intersection = match scene.trace(&ray) {
+                Some(intersection) => intersection,
+                None => {
+                    if !(lights_sampled && scene.is_environment_sampled()) {
+                        radiance = radiance + throughput.times(&scene.get_background(ray.get_direction()));
+                    }
+                    break;
+                }
+            };
+            let object = intersection.get_object();
+            let material = object.get_material();
+            let hit_point = ray.get_origin().plus(&ray.get_direction().factor(intersection.get_distance()));
+            let normal = intersection.surface_normal(&hit_point).normalize();
+            let facing_normal = if ray.get_direction().dot(&normal) > 0.0 { normal.neg() } else { normal.clone() };
+            let surface_color = intersection.get_texture_color(&hit_point);
+
+            if !(lights_sampled && scene.is_light_source(object)) {
+                radiance = radiance + throughput.times(material.get_emission());
+            }
+            lights_sampled = false;
+
+            // reflective and refractive surfaces randomly pick one of their components
+            let next_ray = match material.get_surface_type() {
+                SurfaceType::Reflective { reflectivity } if rng.next_f64() < *reflectivity => {
+                    Ray::from_reflection(&facing_normal, ray.get_direction(), &hit_point, SHADOW_BIAS)
+                }
+                SurfaceType::Refractive { index, transparency } if rng.next_f64() < *transparency => {
+                    let kr = fresnel(ray.get_direction(), &normal, *index);
+                    let refraction = if rng.next_f64() < kr {
+                        None
+                    } else {
+                        Ray::from_refraction(&normal, ray.get_direction(), &hit_point, SHADOW_BIAS, *index)
+                    };
+                    match refraction {
+                        Some(refraction) => {
+                            throughput = throughput.times(&surface_color);
+                            refraction
+                        }
+                        None => Ray::from_reflection(&facing_normal, ray.get_direction(), &hit_point, SHADOW_BIAS),
+                    }
+                }
+                SurfaceType::Microfacet(microfacet) => {
+                    let to_viewer = ray.get_direction().neg();
+                    let mut direct = Color::black();
+                    for light in scene.get_lights() {
+                        direct = direct + scene.reflected_light(light.as_ref(), &hit_point, &facing_normal,
+                                                                ray.get_time(), rng, |to_light| {
+                            microfacet.brdf(&surface_color, &facing_normal, &to_viewer, to_light).factor(PI)
+                        });
+                    }
+                    radiance = radiance + throughput.times(&direct);
+                    lights_sampled = true;
+
+                    let (choice, u, v) = (rng.next_f64(), rng.next_f64(), rng.next_f64());
+                    match microfacet.sample(&surface_color, &facing_normal, &to_viewer, choice, u, v) {
+                        Some(sample) => {
+                            throughput = throughput.times(sample.get_weight());
+                            Ray::from(hit_point.plus(&facing_normal.factor(BOUNCE_BIAS)), sample.get_direction().clone())
+                        }
+                        None => break,
+                    }
+                }
+                _ => {
+                    let mut direct = Color::black();
+                    // highlights are only gathered from the lights, bounces stay diffuse
+                    let mut highlights = Color::black();
+                    let to_viewer = ray.get_direction().neg();
+                    for light in scene.get_lights() {
+                        let samples = scene.light_samples(light.as_ref(), &hit_point, &facing_normal, ray.get_time(),
+                                                          rng);
+                        direct = direct + scene.arriving_light(light.as_ref(), &samples, &facing_normal,
+                                                               material.get_glossiness());
+                        if material.has_highlights() {
+                            highlights = highlights + scene.reflected_from(light.as_ref(), &samples, &facing_normal,
+                                                                           |to_light| {
+                                material.highlight(&facing_normal, &to_viewer, to_light)
+                            });
+                        }
+                    }
+                    radiance = radiance + throughput.times(&highlights);
+                    throughput = throughput.times(&surface_color);
+                    radiance = radiance + throughput.times(&direct);
+                    lights_sampled = true;
+
+                    // the cosine of the sampled density cancels with the cosine of the diffuse surface
+                    let direction = cosine_hemisphere(&facing_normal, rng.next_f64(), rng.next_f64());
+                    Ray::from(hit_point.plus(&facing_normal.factor(BOUNCE_BIAS)), direction)
+                }
+            };
+
+            if depth + 1 >= self.roulette_depth {
+                let [r, g, b] = throughput.get();
+                let survival = r.max(g).max(b).min(1.0);
+                if survival <= 0.0 || rng.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput.factor(1.0 / survival);
+            }
+            ray = next_ray.with_time(ray.get_time());
+        }
+        radiance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::material::Material;
+    use crate::objects::sphere::Sphere;
+    use crate::vector::Vector;
+
+    #[test]
+    fn test_glowing_furnace() {
+        // inside a closed sphere reflecting half of the light and emitting 1, every bounce adds
+        // half of the previous one, so the radiance converges to 1 / (1 - 0.5)
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 60.0, 1.0);
+        let mut scene = Scene::new(camera);
+        let mut material = Material::new_constant(Color::new(0.5, 0.5, 0.5), SurfaceType::Diffuse, 1.0, 1.0);
+        material.set_emission(Color::new(1.0, 1.0, 1.0));
+        scene.add_object(Box::new(Sphere::new(Vector::zero(), 10.0, material)));
+
+        let tracer = PathTracer::default();
+        let mut rng = Rng::new(5);
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        let count = 5000;
+        let mut total = 0.0;
+        for _ in 0..count {
+            total += tracer.radiance(&scene, &ray, &mut rng).get()[0];
+        }
+        assert!((total / count as f64 - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_sampled_sky_is_counted_once() {
+        use crate::environment::SolidEnvironment;
+        use crate::objects::plane::Plane;
+        use std::sync::Arc;
+
+        // under a white sky, a floor reflecting half of the light is half as bright
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 60.0, 1.0);
+        let mut scene = Scene::new(camera);
+        scene.set_environment(Arc::new(SolidEnvironment::new(Color::new(1.0, 1.0, 1.0))), Some(4));
+        scene.add_object(Box::new(Plane::new(
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            Material::new_constant(Color::new(0.5, 0.5, 0.5), SurfaceType::Diffuse, 1.0, 1.0),
+        )));
+
+        let tracer = PathTracer::default();
+        let mut rng = Rng::new(2);
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, -1.0, -1.0).normalize());
+        let count = 2000;
+        let mut total = 0.0;
+        for _ in 0..count {
+            total += tracer.radiance(&scene, &ray, &mut rng).get()[0];
+        }
+        assert!((total / count as f64 - 0.5).abs() < 0.02, "{}", total / count as f64);
+    }
+}
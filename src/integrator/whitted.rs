@@ -0,0 +1,202 @@
+use std::f64::consts::PI;
+
+use crate::base::{Color, Intersection, Ray};
+use crate::integrator::{fresnel, Integrator};
+use crate::material::SurfaceType;
+use crate::sampling::Rng;
+use crate::scene::{Scene, SHADOW_BIAS};
+
+/// Direct lighting from the scene lights plus perfect mirror reflection and refraction.
+pub struct WhittedIntegrator {
+    max_depth: u32,
+}
+
+impl WhittedIntegrator {
+    /// `max_depth` limits the number of reflection and refraction bounces.
+    pub fn new(max_depth: u32) -> WhittedIntegrator {
+        WhittedIntegrator {
+            max_depth: max_depth,
+        }
+    }
+    fn get_color(&self, scene: &Scene, ray: &Ray, intersection: &Intersection, depth: u32, rng: &mut Rng) -> Color {
+        let hit_point = ray
+            .get_origin()
+            .plus(&ray.get_direction()
+                .factor(intersection.get_distance()));
+        let surface_normal = intersection.surface_normal(&hit_point);
+        // two-sided surfaces like triangles and disks are lit and reflect on the side the ray came from
+        let facing_normal = if ray.get_direction().dot(&surface_normal) > 0.0 {
+            surface_normal.neg()
+        } else {
+            surface_normal.clone()
+        };
+        let object = intersection.get_object();
+
+        // glowing surfaces add their own light on top of what they reflect or let through
+        let emission = object.get_material().get_emission().clone();
+        let mut color = Color::black();
+
+        if let SurfaceType::Microfacet(microfacet) = object.get_material().get_surface_type() {
+            let to_viewer = ray.get_direction().neg();
+            let base_color = intersection.get_texture_color(&hit_point);
+            for light in scene.get_lights() {
+                color = color + scene.reflected_light(light.as_ref(), &hit_point, &facing_normal, ray.get_time(), rng,
+                                                      |to_light| {
+                    microfacet.brdf(&base_color, &facing_normal, &to_viewer, to_light).factor(PI)
+                });
+            }
+            // only the mirror direction can be followed, so reflections fade out on rough surfaces
+            let smoothness = (1.0 - microfacet.get_roughness()).powi(2);
+            if smoothness > 0.0 {
+                let reflection_ray = Ray::from_reflection(&facing_normal, ray.get_direction(), &hit_point, SHADOW_BIAS)
+                    .with_time(ray.get_time());
+                let fresnel = microfacet.fresnel(&base_color, facing_normal.dot(&to_viewer));
+                color = color + self.cast_ray(scene, &reflection_ray, depth + 1, rng).times(&fresnel).factor(smoothness);
+            }
+            return color + emission;
+        }
+
+        for light in scene.get_lights() {
+            let material = object.get_material();
+            // the diffuse light and the highlights share the shadow rays
+            let samples = scene.light_samples(light.as_ref(), &hit_point, &facing_normal, ray.get_time(), rng);
+            let light_arriving = scene.arriving_light(light.as_ref(), &samples, &facing_normal,
+                                                      material.get_glossiness());
+            let light_reflected = 1.0; // todo: implementiraj
+
+            let obj_color = intersection.get_texture_color(&hit_point);
+            color = color + light_arriving.times(&obj_color).factor(light_reflected);
+
+            if material.has_highlights() {
+                let to_viewer = ray.get_direction().neg();
+                color = color + scene.reflected_from(light.as_ref(), &samples, &facing_normal, |to_light| {
+                    material.highlight(&facing_normal, &to_viewer, to_light)
+                });
+            }
+        }
+
+        if let SurfaceType::Reflective { reflectivity } = object.get_material().get_surface_type() {
+            let reflection_ray = Ray::from_reflection(&facing_normal, ray.get_direction(),
+                                                      &hit_point, SHADOW_BIAS)
+                .with_time(ray.get_time());
+            let reflect_color = self.cast_ray(scene, &reflection_ray, depth + 1, rng);
+            color = color.factor(1.0 - *reflectivity) + reflect_color.factor(*reflectivity);
+        }
+
+        if let SurfaceType::Refractive { index, transparency } = object.get_material().get_surface_type() {
+            let kr = fresnel(ray.get_direction(), &surface_normal, *index);
+
+            let mut refraction_color = Color::black();
+            if kr < 1.0 {
+                if let Some(transmission_ray) = Ray::from_refraction(&surface_normal, ray.get_direction(),
+                                                                     &hit_point, SHADOW_BIAS, *index) {
+                    let transmission_ray = transmission_ray.with_time(ray.get_time());
+                    refraction_color = self.cast_ray(scene, &transmission_ray, depth + 1, rng)
+                        .times(&intersection.get_texture_color(&hit_point));
+                }
+            }
+
+            let reflection_ray = Ray::from_reflection(&facing_normal, ray.get_direction(),
+                                                      &hit_point, SHADOW_BIAS)
+                .with_time(ray.get_time());
+            let reflection_color = self.cast_ray(scene, &reflection_ray, depth + 1, rng);
+
+            color = color.factor(1.0 - *transparency)
+                + (reflection_color.factor(kr) + refraction_color.factor(1.0 - kr)).factor(*transparency);
+        }
+        color + emission
+    }
+    fn cast_ray(&self, scene: &Scene, ray: &Ray, depth: u32, rng: &mut Rng) -> Color {
+        let black_color = scene.get_background(ray.get_direction());
+        if depth >= self.max_depth {
+            return black_color;
+        }
+
+        let intersection = scene.trace(ray);
+        intersection.map(|i| self.get_color(scene, ray, &i, depth, rng))
+            .unwrap_or(black_color)
+    }
+}
+
+impl Default for WhittedIntegrator {
+    fn default() -> WhittedIntegrator {
+        WhittedIntegrator::new(5)
+    }
+}
+
+impl Integrator for WhittedIntegrator {
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut Rng) -> Color {
+        if let Some(intersection) = scene.trace(ray) {
+            self.get_color(scene, ray, &intersection, 1, rng)
+        } else {
+            scene.get_background(ray.get_direction())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::camera::Camera;
+    use crate::environment::SolidEnvironment;
+    use crate::lighting::spherical::SphericalLight;
+    use crate::material::Material;
+    use crate::objects::mesh::{Face, Mesh};
+    use crate::objects::plane::Plane;
+    use crate::vector::Vector;
+
+    #[test]
+    fn test_triangle_lit_from_behind() {
+        // the triangle faces away from the camera and the light, which both see its back
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 60.0, 1.0);
+        let mut scene = Scene::new(camera);
+        scene.add_mesh(Mesh::new(
+            vec![Vector::new(-1.0, -1.0, -5.0), Vector::new(0.0, 1.0, -5.0), Vector::new(1.0, -1.0, -5.0)],
+            vec![],
+            vec![],
+            vec![Face { positions: [0, 1, 2], normals: None, uvs: None }],
+            Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0),
+        ));
+        scene.add_light(Box::new(SphericalLight::new(Vector::new(0.0, 0.0, -1.0), Color::new(1.0, 1.0, 1.0),
+                                                     100.0)));
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        let hit = scene.trace(&ray).unwrap();
+        assert!(hit.surface_normal(&Vector::new(0.0, 0.0, -5.0)).get_z() < 0.0);
+
+        let color = WhittedIntegrator::default().radiance(&scene, &ray, &mut Rng::new(1));
+        assert!(color.get()[0] > 0.0, "{:?}", color);
+    }
+
+    fn mirror_scene(material: Material) -> Scene {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 60.0, 1.0);
+        let mut scene = Scene::new(camera);
+        scene.set_environment(Arc::new(SolidEnvironment::new(Color::new(1.0, 1.0, 1.0))), None);
+        scene.add_object(Box::new(Plane::new(Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0), material)));
+        scene
+    }
+
+    #[test]
+    fn test_mirror_without_lights() {
+        // the mirror shows the sky even when there are no lights to add to it
+        let mirror = Material::new_constant(Color::new(0.0, 0.0, 0.0), SurfaceType::Reflective { reflectivity: 0.5 },
+                                            1.0, 1.0);
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, -1.0, -1.0).normalize());
+        let color = WhittedIntegrator::default().radiance(&mirror_scene(mirror), &ray, &mut Rng::new(1));
+        assert!((color.get()[0] - 0.5).abs() < 1e-9, "{:?}", color);
+    }
+
+    #[test]
+    fn test_glowing_mirror() {
+        // the reflection does not dim the light of the surface itself
+        let mut mirror = Material::new_constant(Color::new(0.0, 0.0, 0.0),
+                                                SurfaceType::Reflective { reflectivity: 0.5 }, 1.0, 1.0);
+        mirror.set_emission(Color::new(1.0, 1.0, 1.0));
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, -1.0, -1.0).normalize());
+        let color = WhittedIntegrator::default().radiance(&mirror_scene(mirror), &ray, &mut Rng::new(1));
+        assert!((color.get()[0] - 1.5).abs() < 1e-9, "{:?}", color);
+    }
+}
@@ -1,11 +1,23 @@
+#![allow(clippy::redundant_field_names)]
+
 extern crate image;
 
 pub mod base;
+pub mod bvh;
+pub mod camera;
+pub mod environment;
+pub mod framebuffer;
+pub mod integrator;
 pub mod scene;
+pub mod scene_file;
 pub mod vector;
 pub mod objects;
 pub mod lighting;
 pub mod material;
+pub mod sampling;
+pub mod settings;
+pub mod transform;
+pub mod obj;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+use crate::base::{Color, Colorable};
+use crate::lighting::{surface_sample, LightSample, Lighting};
+
+/// Round light shining towards its normal.
+pub struct DiskLight {
+    center: Vector,
+    normal: Vector,
+    radius: f64,
+    tangent: Vector,
+    bitangent: Vector,
+    color: Color,
+    intensity: f64,
+    samples: u32,
+}
+
+impl DiskLight {
+    pub fn new(center: Vector, normal: Vector, radius: f64, color: Color, intensity: f64,
+               samples: u32) -> DiskLight {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        DiskLight {
+            center: center,
+            normal: normal,
+            radius: radius,
+            tangent: tangent,
+            bitangent: bitangent,
+            color: color,
+            intensity: intensity,
+            samples: samples,
+        }
+    }
+}
+
+impl Colorable for DiskLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for DiskLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        surface_sample(&self.center, &self.normal, hit_point, self.intensity).get_intensity()
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.center.minus(hit_point)
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    fn sample(&self, hit_point: &Vector, u: f64, v: f64, _time: f64) -> LightSample {
+        // the square root spreads the samples uniformly over the area
+        let r = self.radius * u.sqrt();
+        let phi = 2.0 * PI * v;
+        let point = self.center
+            .plus(&self.tangent.factor(r * phi.cos()))
+            .plus(&self.bitangent.factor(r * phi.sin()));
+        surface_sample(&point, &self.normal, hit_point, self.intensity)
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use crate::base::{Color, Colorable, Drawable};
+use crate::lighting::{surface_sample, LightSample, Lighting};
+use crate::vector::Vector;
+
+/// Distance the sampled points are moved off the surface towards the lit point, so that shadow
+/// rays stop just short of the glowing object instead of being blocked by it.
+const SURFACE_OFFSET: f64 = 1e-7;
+
+/// Light given off by glowing objects, a single shape or all the triangles of a mesh. Points are
+/// picked uniformly over the total area, and every point glows towards the side it is seen from.
+/// The back of closed shapes is hidden by their front, which the shadow rays take care of.
+pub struct EmissiveLight {
+    shapes: Vec<Arc<dyn Drawable>>,
+    /// Area of the shapes up to and including each one.
+    cumulative_areas: Vec<f64>,
+    color: Color,
+    samples: u32,
+}
+
+impl EmissiveLight {
+    /// `color` is the radiance of the surface, the shapes have to support `sample_surface`.
+    pub fn new(shapes: Vec<Arc<dyn Drawable>>, color: Color, samples: u32) -> EmissiveLight {
+        let mut area = 0.0;
+        let cumulative_areas = shapes.iter()
+            .map(|shape| {
+                area += shape.surface_area().unwrap_or(0.0);
+                area
+            })
+            .collect();
+        EmissiveLight {
+            shapes: shapes,
+            cumulative_areas: cumulative_areas,
+            color: color,
+            samples: samples,
+        }
+    }
+    pub fn get_area(&self) -> f64 {
+        self.cumulative_areas.last().cloned().unwrap_or(0.0)
+    }
+}
+
+impl Colorable for EmissiveLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for EmissiveLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        self.sample(hit_point, 0.5, 0.5, 0.0).get_intensity()
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.sample(hit_point, 0.5, 0.5, 0.0).get_direction().clone()
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    fn sample(&self, hit_point: &Vector, u: f64, v: f64, time: f64) -> LightSample {
+        let area = self.get_area();
+        if area <= 0.0 {
+            return LightSample::new(Vector::zero(), 0.0);
+        }
+        // `u` picks a shape by area and is then stretched back over the share of that shape
+        let target = u * area;
+        let index = self.cumulative_areas.partition_point(|&end| end <= target).min(self.shapes.len() - 1);
+        let start = if index == 0 { 0.0 } else { self.cumulative_areas[index - 1] };
+        let u = ((target - start) / (self.cumulative_areas[index] - start)).min(1.0);
+
+        let (point, normal) = match self.shapes[index].sample_surface(u, v, time) {
+            Some(sample) => sample,
+            None => return LightSample::new(Vector::zero(), 0.0),
+        };
+        let normal = if normal.dot(&hit_point.minus(&point)) < 0.0 { normal.neg() } else { normal };
+        let point = point.plus(&normal.factor(SURFACE_OFFSET));
+        // a radiance of 1 over the whole area delivers what an area light of intensity `area` does
+        surface_sample(&point, &normal, hit_point, area)
+    }
+}
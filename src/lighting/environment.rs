@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::base::{Color, Colorable};
+use crate::environment::{from_lat_long, Environment};
+use crate::lighting::{LightSample, Lighting, FAR_AWAY};
+use crate::vector::Vector;
+
+/// Share of the samples spread evenly over all directions, so that dark parts of the environment
+/// are not missed completely.
+const UNIFORM_SHARE: f64 = 0.01;
+
+/// Lights the scene with the environment, sending the shadow rays preferably into its bright
+/// parts. The directions are picked from a latitude-longitude grid, first a row and then a cell
+/// in it, each in proportion to the brightness.
+pub struct EnvironmentLight {
+    environment: Arc<dyn Environment>,
+    columns: usize,
+    rows: usize,
+    /// Weight of the rows up to and including each one.
+    row_weights: Vec<f64>,
+    /// Weight of the cells in a row up to and including each one, row after row.
+    cell_weights: Vec<f64>,
+    color: Color,
+    samples: u32,
+}
+
+impl EnvironmentLight {
+    pub fn new(environment: Arc<dyn Environment>, samples: u32) -> EnvironmentLight {
+        let (columns, rows) = environment.sampling_resolution();
+        let (columns, rows) = (columns.max(1), rows.max(1));
+
+        let mut weights = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            let v = (row as f64 + 0.5) / rows as f64;
+            // cells near the poles cover a smaller solid angle
+            let area = (v * PI).sin();
+            for column in 0..columns {
+                let u = (column as f64 + 0.5) / columns as f64;
+                weights.push(environment.radiance(&from_lat_long(u, v)).luminance().max(0.0) * area);
+            }
+        }
+        let average = weights.iter().sum::<f64>() / weights.len() as f64;
+        let floor = if average > 0.0 { average * UNIFORM_SHARE } else { 1.0 };
+
+        let mut row_weights = Vec::with_capacity(rows);
+        let mut cell_weights = Vec::with_capacity(columns * rows);
+        let mut total = 0.0;
+        for row in weights.chunks(columns) {
+            let mut row_total = 0.0;
+            for weight in row {
+                row_total += weight + floor;
+                cell_weights.push(row_total);
+            }
+            total += row_total;
+            row_weights.push(total);
+        }
+        EnvironmentLight {
+            environment: environment,
+            columns: columns,
+            rows: rows,
+            row_weights: row_weights,
+            cell_weights: cell_weights,
+            color: Color::new(1.0, 1.0, 1.0),
+            samples: samples,
+        }
+    }
+    /// Density of picking the direction in the cell, with respect to solid angle.
+    fn pdf(&self, row: usize, column: usize) -> f64 {
+        let cells = &self.cell_weights[row * self.columns..(row + 1) * self.columns];
+        let weight = cells[column] - if column == 0 { 0.0 } else { cells[column - 1] };
+        let total = self.row_weights[self.rows - 1];
+        let top = (row as f64 / self.rows as f64 * PI).cos();
+        let bottom = ((row + 1) as f64 / self.rows as f64 * PI).cos();
+        let solid_angle = 2.0 * PI / self.columns as f64 * (top - bottom);
+        weight / total / solid_angle
+    }
+}
+
+/// Finds the entry of the running sums `cumulative` that `value` in `[0, 1)` falls into, and where
+/// in that entry it lies, again in `[0, 1)`.
+fn pick(cumulative: &[f64], value: f64) -> (usize, f64) {
+    let total = cumulative[cumulative.len() - 1];
+    let target = value * total;
+    let index = cumulative.partition_point(|&end| end <= target).min(cumulative.len() - 1);
+    let start = if index == 0 { 0.0 } else { cumulative[index - 1] };
+    (index, ((target - start) / (cumulative[index] - start)).clamp(0.0, 1.0))
+}
+
+impl Colorable for EnvironmentLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for EnvironmentLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        self.sample(hit_point, 0.5, 0.5, 0.0).get_intensity()
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.sample(hit_point, 0.5, 0.5, 0.0).get_direction().clone()
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    fn sample(&self, _hit_point: &Vector, u: f64, v: f64, _time: f64) -> LightSample {
+        let (row, u) = pick(&self.row_weights, u);
+        let (column, v) = pick(&self.cell_weights[row * self.columns..(row + 1) * self.columns], v);
+
+        // uniform in the cosine of the polar angle is uniform in solid angle within the cell
+        let top = (row as f64 / self.rows as f64 * PI).cos();
+        let bottom = ((row + 1) as f64 / self.rows as f64 * PI).cos();
+        let cos_theta = top + (bottom - top) * u;
+        let lat_long_v = cos_theta.clamp(-1.0, 1.0).acos() / PI;
+        let direction = from_lat_long((column as f64 + v) / self.columns as f64, lat_long_v);
+
+        // like for the area lights, a white surface reflects the radiance times the solid angle
+        // over π, which the sample stands in for by dividing by its density
+        let intensity = 1.0 / (PI * self.pdf(row, column));
+        LightSample::tinted(direction.factor(FAR_AWAY), intensity, self.environment.radiance(&direction))
+    }
+}
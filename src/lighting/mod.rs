@@ -1,10 +1,236 @@
 use crate::vector::Vector;
-use crate::base::Colorable;
+use crate::base::{Color, Colorable};
 
 pub mod directional;
+pub mod disk;
+pub mod emissive;
+pub mod environment;
+pub mod rectangle;
+pub mod sphere;
 pub mod spherical;
+pub mod spot;
+pub mod sun;
 
-pub trait Lighting: Colorable {
+/// Length of the shadow rays towards lights that are infinitely far away.
+pub(crate) const FAR_AWAY: f64 = 1e12;
+
+/// A point on a light as seen from a shading point.
+pub struct LightSample {
+    direction: Vector,
+    intensity: f64,
+    tint: Color,
+}
+
+impl LightSample {
+    /// `direction` points from the shading point to the sampled point, its length is the distance
+    /// the shadow ray has to cover.
+    pub fn new(direction: Vector, intensity: f64) -> LightSample {
+        LightSample::tinted(direction, intensity, Color::new(1.0, 1.0, 1.0))
+    }
+    /// Sample of a light whose color changes over its surface, `tint` multiplies the color of the
+    /// light.
+    pub fn tinted(direction: Vector, intensity: f64, tint: Color) -> LightSample {
+        LightSample {
+            direction: direction,
+            intensity: intensity,
+            tint: tint,
+        }
+    }
+    pub fn get_direction(&self) -> &Vector {
+        &self.direction
+    }
+    pub fn get_intensity(&self) -> f64 {
+        self.intensity
+    }
+    pub fn get_tint(&self) -> &Color {
+        &self.tint
+    }
+}
+
+pub trait Lighting: Colorable + Send + Sync {
     fn get_intensity(&self, hit_point: &Vector) -> f64;
     fn get_direction_to_light(&self, hit_point: &Vector) -> Vector;
+    /// Number of shadow rays averaged to find how much of the light reaches a point.
+    fn get_sample_count(&self) -> u32 {
+        1
+    }
+    /// Picks the point on the light corresponding to `(u, v)` in `[0, 1)²` at `time`, which only
+    /// matters for lights that move. The intensity of the sample is what the whole light would
+    /// deliver if it all came from that point.
+    fn sample(&self, hit_point: &Vector, _u: f64, _v: f64, _time: f64) -> LightSample {
+        LightSample::new(self.get_direction_to_light(hit_point), self.get_intensity(hit_point))
+    }
+}
+
+/// Sample of a flat light emitting `intensity` from its front side with a Lambertian profile.
+fn surface_sample(point: &Vector, normal: &Vector, hit_point: &Vector, intensity: f64) -> LightSample {
+    let direction = point.minus(hit_point);
+    let distance = direction.euclidian_distance();
+    let cos_light = -normal.dot(&direction) / distance;
+    if cos_light <= 0.0 {
+        return LightSample::new(direction, 0.0);
+    }
+    LightSample::new(direction, intensity * cos_light / (::std::f64::consts::PI * distance * distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::disk::DiskLight;
+    use crate::lighting::emissive::EmissiveLight;
+    use crate::lighting::environment::EnvironmentLight;
+    use crate::lighting::rectangle::RectangleLight;
+    use crate::lighting::sphere::SphereLight;
+    use crate::lighting::spot::SpotLight;
+    use crate::lighting::sun::SunLight;
+    use crate::material::Texture;
+
+    const POINTS: [(f64, f64); 4] = [(0.1, 0.2), (0.5, 0.5), (0.9, 0.3), (0.0, 0.99)];
+
+    #[test]
+    fn test_samples_lie_on_the_light() {
+        let hit_point = Vector::new(0.0, 0.0, 0.0);
+        let center = Vector::new(1.0, 4.0, -2.0);
+        let rectangle = RectangleLight::new(center.clone(), Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0),
+                                            Color::new(1.0, 1.0, 1.0), 10.0, 4);
+        let disk = DiskLight::new(center.clone(), Vector::new(0.0, -1.0, 0.0), 0.5, Color::new(1.0, 1.0, 1.0),
+                                  10.0, 4);
+        let sphere = SphereLight::new(center.clone(), 0.5, Color::new(1.0, 1.0, 1.0), 10.0, 4);
+
+        for &(u, v) in POINTS.iter() {
+            let point = rectangle.sample(&hit_point, u, v, 0.0).get_direction().clone();
+            assert!((point.get_x() - 1.0).abs() <= 1.0 && (point.get_z() + 2.0).abs() <= 0.5);
+            assert!((point.get_y() - 4.0).abs() < 1e-9);
+
+            let point = disk.sample(&hit_point, u, v, 0.0).get_direction().clone();
+            assert!(point.minus(&center).euclidian_distance() <= 0.5 + 1e-9);
+            assert!((point.get_y() - 4.0).abs() < 1e-9);
+
+            let point = sphere.sample(&hit_point, u, v, 0.0).get_direction().clone();
+            assert!((point.minus(&center).euclidian_distance() - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_sun_samples_stay_in_the_disk() {
+        let direction = Vector::new(1.0, 2.0, -1.0).normalize();
+        let sun = SunLight::new(direction.clone(), 0.5, Color::new(1.0, 1.0, 1.0), 10.0, 4);
+        for &(u, v) in POINTS.iter() {
+            let sample = sun.sample(&Vector::zero(), u, v, 0.0);
+            let cos = sample.get_direction().normalize().dot(&direction);
+            assert!(cos >= 0.25_f64.to_radians().cos() - 1e-12);
+            assert!(sample.get_direction().euclidian_distance() > 1e6);
+            assert_eq!(sample.get_intensity(), 10.0);
+        }
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let mut spot = SpotLight::new(Vector::new(0.0, 4.0, 0.0), Vector::new(0.0, -1.0, 0.0),
+                                      &Vector::new(0.0, 0.0, -1.0), 20.0, 30.0, Color::new(1.0, 1.0, 1.0), 10.0);
+        let center = spot.get_intensity(&Vector::zero());
+        assert!((center - 10.0 / (4.0 * ::std::f64::consts::PI * 16.0)).abs() < 1e-12);
+        // 25 degrees off the axis is half way between the cones
+        let edge = spot.get_intensity(&Vector::new(4.0 * 25.0_f64.to_radians().tan(), 0.0, 0.0));
+        let expected = center * 25.0_f64.to_radians().cos().powi(2);
+        assert!(edge > 0.1 * expected && edge < 0.9 * expected);
+        assert_eq!(spot.get_intensity(&Vector::new(4.0, 0.0, 0.0)), 0.0);
+        assert_eq!(spot.get_intensity(&Vector::new(0.0, 8.0, 0.0)), 0.0);
+
+        // looking down with the top of the gobo towards -z, its left half lands towards -x
+        spot.set_gobo(Box::new(HalvesTexture));
+        let left = spot.sample(&Vector::new(-0.5, 0.0, 0.0), 0.5, 0.5, 0.0).get_tint().clone();
+        let right = spot.sample(&Vector::new(0.5, 0.0, 0.0), 0.5, 0.5, 0.0).get_tint().clone();
+        assert_eq!(left, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(right, Color::new(0.0, 0.0, 1.0));
+
+        // turned upside down the halves swap
+        let mut turned = SpotLight::new(Vector::new(0.0, 4.0, 0.0), Vector::new(0.0, -1.0, 0.0),
+                                        &Vector::new(0.0, 0.0, 1.0), 20.0, 30.0, Color::new(1.0, 1.0, 1.0), 10.0);
+        turned.set_gobo(Box::new(HalvesTexture));
+        assert_eq!(turned.sample(&Vector::new(-0.5, 0.0, 0.0), 0.5, 0.5, 0.0).get_tint(), &Color::new(0.0, 0.0, 1.0));
+
+        // a spot aimed along -z keeps the gobo upright, with its left half towards -x
+        let mut level = SpotLight::new(Vector::new(0.0, 0.0, 4.0), Vector::new(0.0, 0.0, -1.0),
+                                       &Vector::new(0.0, 1.0, 0.0), 20.0, 30.0, Color::new(1.0, 1.0, 1.0), 10.0);
+        level.set_gobo(Box::new(HalvesTexture));
+        assert_eq!(level.sample(&Vector::new(-0.5, 0.0, 0.0), 0.5, 0.5, 0.0).get_tint(), &Color::new(1.0, 0.0, 0.0));
+    }
+
+    struct HalvesTexture;
+
+    impl Texture for HalvesTexture {
+        fn get_color(&self, x: f64, _y: f64) -> Color {
+            if x < 0.5 { Color::new(1.0, 0.0, 0.0) } else { Color::new(0.0, 0.0, 1.0) }
+        }
+    }
+
+    #[test]
+    fn test_back_side_is_dark() {
+        let rectangle = RectangleLight::new(Vector::zero(), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0),
+                                            Color::new(1.0, 1.0, 1.0), 10.0, 4);
+        assert_eq!(rectangle.get_normal(), &Vector::new(0.0, -1.0, 0.0));
+        assert!(rectangle.sample(&Vector::new(0.0, -1.0, 0.0), 0.3, 0.3, 0.0).get_intensity() > 0.0);
+        assert_eq!(rectangle.sample(&Vector::new(0.0, 1.0, 0.0), 0.3, 0.3, 0.0).get_intensity(), 0.0);
+    }
+
+    #[test]
+    fn test_distant_sphere_light_acts_like_a_point() {
+        let sphere = SphereLight::new(Vector::new(0.0, 1000.0, 0.0), 0.1, Color::new(1.0, 1.0, 1.0), 10.0, 4);
+        let expected = 10.0 / (4.0 * ::std::f64::consts::PI * 1000.0 * 1000.0);
+        let intensity = sphere.sample(&Vector::zero(), 0.4, 0.7, 0.0).get_intensity();
+        assert!((intensity - expected).abs() / expected < 1e-4);
+    }
+
+    #[test]
+    fn test_glowing_disk_acts_like_a_disk_light() {
+        use crate::material::{Material, SurfaceType};
+        use crate::objects::disk::Disk;
+        use std::sync::Arc;
+
+        let center = Vector::new(0.0, 4.0, 0.0);
+        let down = Vector::new(0.0, -1.0, 0.0);
+        let material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        let glowing = EmissiveLight::new(vec![Arc::new(Disk::new(center.clone(), down.clone(), 0.5, material))],
+                                         Color::new(1.0, 1.0, 1.0), 4);
+        let area = ::std::f64::consts::PI * 0.25;
+        let disk = DiskLight::new(center, down, 0.5, Color::new(1.0, 1.0, 1.0), area, 4);
+
+        for &(u, v) in POINTS.iter() {
+            let expected = disk.sample(&Vector::new(1.0, 0.0, 0.0), u, v, 0.0).get_intensity();
+            let intensity = glowing.sample(&Vector::new(1.0, 0.0, 0.0), u, v, 0.0).get_intensity();
+            assert!((intensity - expected).abs() / expected < 1e-6);
+            // unlike the light, the surface glows on both sides
+            let above = glowing.sample(&Vector::new(1.0, 8.0, 0.0), u, v, 0.0).get_intensity();
+            assert!((above - expected).abs() / expected < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_environment_light() {
+        use crate::environment::{ImageEnvironment, SolidEnvironment};
+        use crate::sampling::{Rng, SamplePattern};
+        use std::sync::Arc;
+
+        // a white sky lights an upward facing white surface as much as it is bright
+        let sky = EnvironmentLight::new(Arc::new(SolidEnvironment::new(Color::new(1.0, 1.0, 1.0))), 1);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let points = SamplePattern::Jittered.generate(4096, &mut Rng::new(1));
+        let lit: f64 = points.iter()
+            .map(|&(u, v)| {
+                let sample = sky.sample(&Vector::zero(), u, v, 0.0);
+                up.dot(&sample.get_direction().normalize()).max(0.0) * sample.get_intensity() * sample.get_tint().get()[0]
+            })
+            .sum::<f64>() / points.len() as f64;
+        assert!((lit - 1.0).abs() < 0.02, "{}", lit);
+
+        // nearly all samples go towards a single bright pixel
+        let mut pixels = vec![Color::new(0.1, 0.1, 0.1); 32 * 16];
+        pixels[4 * 32 + 20] = Color::new(1000.0, 1000.0, 1000.0);
+        let sun = EnvironmentLight::new(Arc::new(ImageEnvironment::new(32, 16, pixels)), 1);
+        let bright = points.iter()
+            .filter(|&&(u, v)| sun.sample(&Vector::zero(), u, v, 0.0).get_tint().get()[0] > 100.0)
+            .count();
+        assert!(bright > points.len() * 9 / 10);
+    }
 }
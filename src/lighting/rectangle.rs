@@ -0,0 +1,60 @@
+use crate::vector::Vector;
+use crate::base::{Color, Colorable};
+use crate::lighting::{surface_sample, LightSample, Lighting};
+
+/// Parallelogram spanned by two edges around its centre. It shines towards `edge_u × edge_v`.
+pub struct RectangleLight {
+    center: Vector,
+    edge_u: Vector,
+    edge_v: Vector,
+    normal: Vector,
+    color: Color,
+    intensity: f64,
+    samples: u32,
+}
+
+impl RectangleLight {
+    pub fn new(center: Vector, edge_u: Vector, edge_v: Vector, color: Color, intensity: f64,
+               samples: u32) -> RectangleLight {
+        let normal = edge_u.cross(&edge_v).normalize();
+        RectangleLight {
+            center: center,
+            edge_u: edge_u,
+            edge_v: edge_v,
+            normal: normal,
+            color: color,
+            intensity: intensity,
+            samples: samples,
+        }
+    }
+    pub fn get_normal(&self) -> &Vector {
+        &self.normal
+    }
+}
+
+impl Colorable for RectangleLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for RectangleLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        surface_sample(&self.center, &self.normal, hit_point, self.intensity).get_intensity()
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.center.minus(hit_point)
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    fn sample(&self, hit_point: &Vector, u: f64, v: f64, _time: f64) -> LightSample {
+        let point = self.center
+            .plus(&self.edge_u.factor(u - 0.5))
+            .plus(&self.edge_v.factor(v - 0.5));
+        surface_sample(&point, &self.normal, hit_point, self.intensity)
+    }
+}
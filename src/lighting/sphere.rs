@@ -0,0 +1,77 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+use crate::base::{Color, Colorable};
+use crate::lighting::{LightSample, Lighting};
+
+/// Glowing ball, unlike `SphericalLight` it has a size and casts soft shadows.
+pub struct SphereLight {
+    center: Vector,
+    radius: f64,
+    color: Color,
+    intensity: f64,
+    samples: u32,
+}
+
+impl SphereLight {
+    pub fn new(center: Vector, radius: f64, color: Color, intensity: f64, samples: u32) -> SphereLight {
+        SphereLight {
+            center: center,
+            radius: radius,
+            color: color,
+            intensity: intensity,
+            samples: samples,
+        }
+    }
+    /// Radiance of the surface when it emits `intensity` evenly in all directions.
+    fn radiance(&self) -> f64 {
+        self.intensity / (4.0 * PI * PI * self.radius * self.radius)
+    }
+}
+
+impl Colorable for SphereLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for SphereLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        let distance = self.center.minus(hit_point).euclidian_distance().max(self.radius);
+        let cos_max = (1.0 - (self.radius / distance).powi(2)).max(0.0).sqrt();
+        self.radiance() * 2.0 * PI * (1.0 - cos_max)
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.center.minus(hit_point)
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    /// Samples the cone of directions in which the sphere is visible, all of them are equally
+    /// bright.
+    fn sample(&self, hit_point: &Vector, u: f64, v: f64, _time: f64) -> LightSample {
+        let to_center = self.center.minus(hit_point);
+        let distance = to_center.euclidian_distance();
+        if distance <= self.radius {
+            return LightSample::new(to_center, self.get_intensity(hit_point));
+        }
+        let axis = to_center.factor(1.0 / distance);
+        let (tangent, bitangent) = axis.orthonormal_basis();
+
+        let cos_max = (1.0 - (self.radius / distance).powi(2)).sqrt();
+        let cos_theta = 1.0 - u * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let direction = axis.factor(cos_theta)
+            .plus(&tangent.factor(sin_theta * phi.cos()))
+            .plus(&bitangent.factor(sin_theta * phi.sin()));
+
+        // distance to the near side of the sphere along the sampled direction
+        let along = distance * cos_theta;
+        let reach = along - (self.radius * self.radius - distance * distance * sin_theta * sin_theta).max(0.0).sqrt();
+        LightSample::new(direction.factor(reach), self.get_intensity(hit_point))
+    }
+}
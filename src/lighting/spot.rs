@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+use crate::base::{Color, Colorable};
+use crate::lighting::{LightSample, Lighting};
+use crate::material::Texture;
+
+/// Point light shining into a cone, like a stage light. Within the inner cone it is as bright as a
+/// `SphereLight` of the same intensity, and it fades out smoothly towards the outer cone.
+pub struct SpotLight {
+    position: Vector,
+    /// Unit vector along the axis of the cone.
+    direction: Vector,
+    /// Directions of the right and the top edge of the gobo, across the cone.
+    right: Vector,
+    vertical: Vector,
+    cos_inner: f64,
+    cos_outer: f64,
+    color: Color,
+    intensity: f64,
+    /// Image projected through the cone, a gobo, stretched over the square around the outer cone.
+    gobo: Option<Box<dyn Texture>>,
+}
+
+impl SpotLight {
+    /// The cone angles are in degrees between the axis and the edge, `inner` at most `outer`. Like
+    /// for the `Camera`, `up` turns the gobo so that its top points that way and must not be
+    /// parallel to the `direction`.
+    pub fn new(position: Vector, direction: Vector, up: &Vector, inner: f64, outer: f64, color: Color,
+               intensity: f64) -> SpotLight {
+        let direction = direction.normalize();
+        let right = direction.cross(up).normalize();
+        let vertical = right.cross(&direction);
+        SpotLight {
+            position: position,
+            direction: direction,
+            right: right,
+            vertical: vertical,
+            cos_inner: inner.min(outer).to_radians().cos(),
+            cos_outer: outer.to_radians().cos(),
+            color: color,
+            intensity: intensity,
+            gobo: None,
+        }
+    }
+    pub fn set_gobo(&mut self, gobo: Box<dyn Texture>) {
+        self.gobo = Some(gobo);
+    }
+    pub fn get_direction(&self) -> &Vector {
+        &self.direction
+    }
+    /// Share of the light reaching `direction` from the light, 1 inside the inner cone and 0 outside
+    /// of the outer one.
+    fn falloff(&self, direction: &Vector) -> f64 {
+        let cos = direction.dot(&self.direction);
+        if cos >= self.cos_inner {
+            return 1.0;
+        }
+        if cos <= self.cos_outer {
+            return 0.0;
+        }
+        let t = (cos - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+    /// Color of the gobo seen in `direction` from the light, where `(0, 0)` is the top left corner
+    /// of the texture when looking along the cone.
+    fn gobo_color(&self, direction: &Vector) -> Color {
+        let gobo = match &self.gobo {
+            Some(gobo) => gobo,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+        let cos = direction.dot(&self.direction);
+        if cos <= 0.0 {
+            return Color::black();
+        }
+        let tan_outer = (1.0 - self.cos_outer * self.cos_outer).max(0.0).sqrt() / self.cos_outer;
+        let x = direction.dot(&self.right) / cos / tan_outer;
+        let y = direction.dot(&self.vertical) / cos / tan_outer;
+        gobo.get_color((x + 1.0) / 2.0, (1.0 - y) / 2.0)
+    }
+}
+
+impl Colorable for SpotLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for SpotLight {
+    fn get_intensity(&self, hit_point: &Vector) -> f64 {
+        let to_point = hit_point.minus(&self.position);
+        let distance = to_point.euclidian_distance();
+        if distance == 0.0 {
+            return 0.0;
+        }
+        self.falloff(&to_point.factor(1.0 / distance)) * self.intensity / (4.0 * PI * distance * distance)
+    }
+
+    fn get_direction_to_light(&self, hit_point: &Vector) -> Vector {
+        self.position.minus(hit_point)
+    }
+
+    fn sample(&self, hit_point: &Vector, _u: f64, _v: f64, _time: f64) -> LightSample {
+        let direction = self.get_direction_to_light(hit_point);
+        let tint = self.gobo_color(&direction.neg().normalize());
+        LightSample::tinted(direction, self.get_intensity(hit_point), tint)
+    }
+}
@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+use crate::base::{Color, Colorable};
+use crate::lighting::{LightSample, Lighting, FAR_AWAY};
+
+/// Light from a distant disk of a given angular size, like the sun. Unlike `DirectionalLight` its
+/// shadows get softer the further they fall from the object casting them.
+pub struct SunLight {
+    /// Unit vector pointing towards the center of the sun.
+    direction: Vector,
+    /// Cosine of the angle between the center and the edge of the disk.
+    cos_radius: f64,
+    color: Color,
+    intensity: f64,
+    samples: u32,
+}
+
+impl SunLight {
+    /// `angular_diameter` is in degrees, 0.53 for the sun seen from the earth.
+    pub fn new(direction: Vector, angular_diameter: f64, color: Color, intensity: f64, samples: u32) -> SunLight {
+        SunLight {
+            direction: direction.normalize(),
+            cos_radius: (angular_diameter.to_radians() / 2.0).cos(),
+            color: color,
+            intensity: intensity,
+            samples: samples,
+        }
+    }
+    pub fn get_direction(&self) -> &Vector {
+        &self.direction
+    }
+}
+
+impl Colorable for SunLight {
+    fn get_color(&self) -> &Color {
+        &self.color
+    }
+}
+
+impl Lighting for SunLight {
+    fn get_intensity(&self, _hit_point: &Vector) -> f64 {
+        self.intensity
+    }
+
+    fn get_direction_to_light(&self, _hit_point: &Vector) -> Vector {
+        self.direction.factor(FAR_AWAY)
+    }
+
+    fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    /// Samples the cone of directions covered by the disk, all of them are equally bright.
+    fn sample(&self, _hit_point: &Vector, u: f64, v: f64, _time: f64) -> LightSample {
+        let (tangent, bitangent) = self.direction.orthonormal_basis();
+        let cos_theta = 1.0 - u * (1.0 - self.cos_radius);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let direction = self.direction.factor(cos_theta)
+            .plus(&tangent.factor(sin_theta * phi.cos()))
+            .plus(&bitangent.factor(sin_theta * phi.sin()));
+        LightSample::new(direction.factor(FAR_AWAY), self.intensity)
+    }
+}
@@ -1,69 +1,175 @@
-use gametest::base::{Color};
-use gametest::vector::Vector;
-use gametest::objects::sphere::Sphere;
-use gametest::scene::Scene;
-use gametest::objects::plane::Plane;
-use gametest::lighting::directional::DirectionalLight;
-use gametest::lighting::spherical::SphericalLight;
-fn main() {
-    let mut scene: Scene = Scene::new(
-        600,
-        400,
-        80.0,
-    );
-    scene.add_object(Box::new(Plane::new(
-        Vector::new(0.0, 1.0, 0.0),
-        Vector::new(0.0, -2.5, 0.0),
-        Material::new(Box::new(CheckeredPatternTexture::new(
-            Color::new(100, 100, 100), 4, 4)),
-    SurfaceType::Reflective {reflectivity: 0.02},1.0, 1.0),
-    )));
+use std::env;
+use std::process;
+use std::sync::Arc;
+
+use gametest::camera::Projection;
+use gametest::environment::ImageEnvironment;
+use gametest::framebuffer::ToneMapping;
+use gametest::scene_file::load_scene;
+
+const USAGE: &str = "Usage: rustracer render <scene.toml> [options]
+
+Options:
+    -o, --output <file>     image to write, defaults to image.png; a .hdr file keeps
+                            the linear colors without tone mapping
+    -w, --width <pixels>    override the image width of the scene
+    -h, --height <pixels>   override the image height of the scene
+    -t, --threads <count>   number of render threads, 0 uses all cores
+    -s, --samples <count>   override the number of samples per pixel
+    -e, --exposure <stops>  override the exposure of the scene
+    --tone-mapping <curve>  override the tone mapping: clamp, reinhard or filmic
+    --projection <name>     override the camera projection: perspective, orthographic,
+                            equidistant, equisolid or equirectangular
+    --environment <file>    surround the scene with an equirectangular .hdr image
+                            that also lights it";
+
+/// Shadow rays towards an environment given on the command line.
+const ENVIRONMENT_SAMPLES: u32 = 16;
+
+struct RenderArgs {
+    scene: String,
+    output: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    threads: Option<usize>,
+    samples: Option<u32>,
+    exposure: Option<f64>,
+    tone_mapping: Option<ToneMapping>,
+    projection: Option<Projection>,
+    environment: Option<String>,
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", option))?;
+    value.parse().map_err(|_| format!("invalid value for {}: {}", option, value))
+}
+
+fn parse_tone_mapping(value: Option<String>) -> Result<ToneMapping, String> {
+    match value.as_deref() {
+        Some("clamp") => Ok(ToneMapping::Clamp),
+        Some("reinhard") => Ok(ToneMapping::Reinhard),
+        Some("filmic") => Ok(ToneMapping::Filmic),
+        Some(value) => Err(format!("invalid value for --tone-mapping: {}", value)),
+        None => Err("missing value for --tone-mapping".to_string()),
+    }
+}
+
+fn parse_projection(value: Option<String>) -> Result<Projection, String> {
+    match value.as_deref() {
+        Some("perspective") => Ok(Projection::Perspective),
+        Some("orthographic") => Ok(Projection::Orthographic),
+        Some("equidistant") => Ok(Projection::Equidistant),
+        Some("equisolid") => Ok(Projection::Equisolid),
+        Some("equirectangular") => Ok(Projection::Equirectangular),
+        Some(value) => Err(format!("invalid value for --projection: {}", value)),
+        None => Err("missing value for --projection".to_string()),
+    }
+}
 
-    scene.add_object(Box::new(Sphere::new(
-        Vector::new(-3.0, 0.0, -10.0),
-        4.0,
-        Material::new_constant(Color::new(255, 0, 0),
-                               SurfaceType::Reflective {reflectivity: 0.04}, 1.0, 1.0),
-    )));
-    scene.add_object(Box::new(Sphere::new(
-        Vector::new(4.0, 0.0, -8.0),
-        2.0,
-        Material::new_constant(Color::new(0, 255, 0),
-                               SurfaceType::Diffuse, 1.0, 3.5)
-    )));
-    scene.add_object(Box::new(Sphere::new(
-        Vector::new(-2.0, 4.0, -6.0),
-        1.5,
-        Material::new_constant(Color::new(128, 128, 128),
-                               SurfaceType::Reflective {reflectivity: 0.05}, 1.0, 1.0)
-    )));
+fn parse_args<I: Iterator<Item=String>>(mut args: I) -> Result<RenderArgs, String> {
+    match args.next().as_deref() {
+        Some("render") => {}
+        Some(command) => return Err(format!("unknown command {}", command)),
+        None => return Err("missing command".to_string()),
+    }
 
-    scene.add_light(Box::new(
-        DirectionalLight::new(
-            Vector::new(1.0, -1.0, -1.0),
-            Color::new(255,0, 0),
-            1.0
-        )
-    ));
-    scene.add_light(Box::new(
-        DirectionalLight::new(
-            Vector::new(-1.0, -1.0, -1.0),
-            Color::new(0,255, 255),
-            1.0
-        )
-    ));
-    scene.add_light(Box::new(
-        SphericalLight::new(
-            Vector::new(0.0, 0.0, -6.0),
-            Color::new(255, 255, 255),
-            6.0
-        )
-    ));
-    let img = scene.render();
-    let result = img.save("image.png");
-    if result.is_err() {
-        eprintln!("Image save failed!");
+    let mut scene = None;
+    let mut render_args = RenderArgs {
+        scene: String::new(),
+        output: "image.png".to_string(),
+        width: None,
+        height: None,
+        threads: None,
+        samples: None,
+        exposure: None,
+        tone_mapping: None,
+        projection: None,
+        environment: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => render_args.output = parse_value(&arg, args.next())?,
+            "-w" | "--width" => render_args.width = Some(parse_value(&arg, args.next())?),
+            "-h" | "--height" => render_args.height = Some(parse_value(&arg, args.next())?),
+            "-t" | "--threads" => render_args.threads = Some(parse_value(&arg, args.next())?),
+            "-s" | "--samples" => render_args.samples = Some(parse_value(&arg, args.next())?),
+            "-e" | "--exposure" => render_args.exposure = Some(parse_value(&arg, args.next())?),
+            "--tone-mapping" => render_args.tone_mapping = Some(parse_tone_mapping(args.next())?),
+            "--projection" => render_args.projection = Some(parse_projection(args.next())?),
+            "--environment" => render_args.environment = Some(parse_value(&arg, args.next())?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if scene.is_none() => scene = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
     }
+    render_args.scene = scene.ok_or_else(|| "missing scene file".to_string())?;
+    Ok(render_args)
 }
 
-use gametest::material::{Material, CheckeredPatternTexture, SurfaceType};
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut scene = match load_scene(&args.scene) {
+        Ok(scene) => scene,
+        Err(error) => {
+            eprintln!("Invalid scene {}: {}", args.scene, error);
+            process::exit(1);
+        }
+    };
+    if args.width.is_some() || args.height.is_some() {
+        let settings = scene.get_settings();
+        let width = args.width.unwrap_or(settings.width);
+        let height = args.height.unwrap_or(settings.height);
+        if width == 0 || height == 0 {
+            eprintln!("Image width and height must be positive");
+            process::exit(2);
+        }
+        scene.set_resolution(width, height);
+    }
+    let mut settings = scene.get_settings().clone();
+    if let Some(threads) = args.threads {
+        settings.threads = threads;
+    }
+    if let Some(samples) = args.samples {
+        if samples == 0 {
+            eprintln!("At least one sample per pixel is needed");
+            process::exit(2);
+        }
+        settings.samples = samples;
+    }
+    if let Some(exposure) = args.exposure {
+        settings.exposure = exposure;
+    }
+    if let Some(tone_mapping) = args.tone_mapping {
+        settings.tone_mapping = tone_mapping;
+    }
+    scene.set_settings(settings);
+    if let Some(projection) = args.projection {
+        scene.get_camera_mut().set_projection(projection);
+    }
+    if let Some(path) = args.environment {
+        match ImageEnvironment::load(&path) {
+            Ok(environment) => scene.set_environment(Arc::new(environment), Some(ENVIRONMENT_SAMPLES)),
+            Err(error) => {
+                eprintln!("Invalid environment {}: {}", path, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    let result = if args.output.to_lowercase().ends_with(".hdr") {
+        scene.render_hdr().save_hdr(&args.output).map_err(|e| e.to_string())
+    } else {
+        scene.render().save(&args.output).map_err(|e| e.to_string())
+    };
+    if let Err(error) = result {
+        eprintln!("Image save failed: {}", error);
+        process::exit(1);
+    }
+}
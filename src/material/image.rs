@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use image::{ImageResult, RgbImage};
+use serde::Deserialize;
+
+use crate::base::Color;
+use crate::material::Texture;
+
+/// How the color between texel centres is found.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    /// Color of the closest texel.
+    Nearest,
+    /// Weighted average of the four surrounding texels.
+    Bilinear,
+}
+
+/// What texture coordinates outside of `[0, 1]` map to.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// Tiles the image.
+    Repeat,
+    /// Extends the border texels.
+    Clamp,
+    /// Tiles the image, flipping every other copy.
+    Mirror,
+}
+
+impl WrapMode {
+    /// Maps a texel index onto `0..size`.
+    fn apply(&self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size { index } else { 2 * size - 1 - index }
+            }
+        };
+        wrapped as u32
+    }
+}
+
+/// Texture backed by an image, stretched over the texture coordinates `[0, 1]²` with `(0, 0)` in
+/// the top left corner of the image.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    texels: Vec<Color>,
+    interpolation: Interpolation,
+    wrap: WrapMode,
+    scale: f64,
+}
+
+impl ImageTexture {
+    /// Takes the texels as sRGB encoded, like colors in PNG or JPEG files are.
+    pub fn new(image: &RgbImage, interpolation: Interpolation, wrap: WrapMode) -> ImageTexture {
+        ImageTexture {
+            width: image.width(),
+            height: image.height(),
+            texels: image.pixels().map(|p| Color::from_srgb8(p[0], p[1], p[2])).collect(),
+            interpolation: interpolation,
+            wrap: wrap,
+            scale: 1.0,
+        }
+    }
+    pub fn open<P: AsRef<Path>>(path: P, interpolation: Interpolation, wrap: WrapMode) -> ImageResult<ImageTexture> {
+        Ok(ImageTexture::new(&image::open(path)?.to_rgb(), interpolation, wrap))
+    }
+    /// Size of a single copy of the image in texture coordinates, 1 by default. Useful for planes,
+    /// whose texture coordinates are distances.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+    fn texel(&self, x: i64, y: i64) -> &Color {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        &self.texels[(y * self.width + x) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn get_color(&self, x: f64, y: f64) -> Color {
+        let u = x / self.scale * self.width as f64;
+        let v = y / self.scale * self.height as f64;
+        match self.interpolation {
+            Interpolation::Nearest => self.texel(u.floor() as i64, v.floor() as i64).clone(),
+            Interpolation::Bilinear => {
+                // texel centres sit at half integer coordinates
+                let (u, v) = (u - 0.5, v - 0.5);
+                let (x0, y0) = (u.floor(), v.floor());
+                let (fx, fy) = (u - x0, v - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = self.texel(x0, y0).factor(1.0 - fx) + self.texel(x0 + 1, y0).factor(fx);
+                let bottom = self.texel(x0, y0 + 1).factor(1.0 - fx) + self.texel(x0 + 1, y0 + 1).factor(fx);
+                top.factor(1.0 - fy) + bottom.factor(fy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// 2x1 image, black on the left and white on the right.
+    fn texture(interpolation: Interpolation, wrap: WrapMode) -> ImageTexture {
+        let mut image = RgbImage::new(2, 1);
+        image.put_pixel(1, 0, Rgb([255, 255, 255]));
+        ImageTexture::new(&image, interpolation, wrap)
+    }
+
+    fn red(texture: &ImageTexture, x: f64) -> f64 {
+        texture.get_color(x, 0.5).get()[0]
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        assert_eq!(WrapMode::Repeat.apply(-1, 4), 3);
+        assert_eq!(WrapMode::Repeat.apply(5, 4), 1);
+        assert_eq!(WrapMode::Clamp.apply(-3, 4), 0);
+        assert_eq!(WrapMode::Clamp.apply(9, 4), 3);
+        assert_eq!(WrapMode::Mirror.apply(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.apply(5, 4), 2);
+        assert_eq!(WrapMode::Mirror.apply(8, 4), 0);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let texture = texture(Interpolation::Nearest, WrapMode::Repeat);
+        assert_eq!(red(&texture, 0.2), 0.0);
+        assert_eq!(red(&texture, 0.7), 1.0);
+        assert_eq!(red(&texture, 1.2), 0.0);
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let clamped = texture(Interpolation::Bilinear, WrapMode::Clamp);
+        assert_eq!(red(&clamped, 0.25), 0.0);
+        assert_eq!(red(&clamped, 0.5), 0.5);
+        assert_eq!(red(&clamped, 0.75), 1.0);
+        assert_eq!(red(&clamped, 1.0), 1.0);
+
+        // past the right edge the black texel of the next copy blends in
+        let repeated = texture(Interpolation::Bilinear, WrapMode::Repeat);
+        assert_eq!(red(&repeated, 1.0), 0.5);
+    }
+}
@@ -0,0 +1,203 @@
+//! Cook-Torrance microfacet reflection with glTF's metallic-roughness parameters.
+
+use std::f64::consts::PI;
+
+use crate::base::Color;
+use crate::sampling::cosine_hemisphere;
+use crate::vector::Vector;
+
+/// Reflectance of dielectrics at normal incidence, the glTF default of 4%.
+const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+/// Smallest GGX alpha, perfectly smooth surfaces would make the distribution a spike.
+const MIN_ALPHA: f64 = 1e-3;
+
+/// Surface with a GGX distribution of microfacets, Smith shadowing and Schlick's Fresnel
+/// approximation. Dielectrics add a Lambertian base below the specular layer, metals colour their
+/// reflection with the base colour.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Microfacet {
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+}
+
+/// Direction picked by `Microfacet::sample`, with the BRDF times the cosine divided by the density
+/// of picking it.
+pub struct MicrofacetSample {
+    direction: Vector,
+    weight: Color,
+}
+
+impl MicrofacetSample {
+    pub fn get_direction(&self) -> &Vector {
+        &self.direction
+    }
+    pub fn get_weight(&self) -> &Color {
+        &self.weight
+    }
+}
+
+impl Microfacet {
+    /// `metallic` and `roughness` are in `[0, 1]`, the roughness is perceptual and squared for
+    /// the GGX alpha. `specular` scales the reflectance of dielectrics, 1 is the glTF default.
+    pub fn new(metallic: f64, roughness: f64, specular: f64) -> Microfacet {
+        Microfacet {
+            metallic: metallic,
+            roughness: roughness,
+            specular: specular,
+        }
+    }
+    pub fn get_metallic(&self) -> f64 {
+        self.metallic
+    }
+    pub fn get_roughness(&self) -> f64 {
+        self.roughness
+    }
+    pub fn get_specular(&self) -> f64 {
+        self.specular
+    }
+    fn alpha(&self) -> f64 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+    /// Reflectance at normal incidence.
+    pub fn f0(&self, base_color: &Color) -> Color {
+        let dielectric = (DIELECTRIC_REFLECTANCE * self.specular).min(1.0);
+        Color::new(dielectric, dielectric, dielectric).factor(1.0 - self.metallic)
+            + base_color.factor(self.metallic)
+    }
+    /// Schlick's approximation of the Fresnel reflectance.
+    pub fn fresnel(&self, base_color: &Color, cos_theta: f64) -> Color {
+        let f0 = self.f0(base_color);
+        let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+        f0.factor(1.0 - weight) + Color::new(weight, weight, weight)
+    }
+    /// GGX (Trowbridge-Reitz) density of microfacets facing along the half vector.
+    fn distribution(&self, n_dot_h: f64) -> f64 {
+        let alpha2 = self.alpha() * self.alpha();
+        let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * denominator * denominator)
+    }
+    /// Height-correlated Smith shadowing and masking, divided by `4 (n·l) (n·v)` as in glTF.
+    fn visibility(&self, n_dot_l: f64, n_dot_v: f64) -> f64 {
+        let alpha2 = self.alpha() * self.alpha();
+        let ggx_v = n_dot_l * (n_dot_v * n_dot_v * (1.0 - alpha2) + alpha2).sqrt();
+        let ggx_l = n_dot_v * (n_dot_l * n_dot_l * (1.0 - alpha2) + alpha2).sqrt();
+        0.5 / (ggx_v + ggx_l)
+    }
+    /// BRDF for light arriving from `to_light` and leaving towards `to_viewer`, both unit vectors
+    /// pointing away from the surface.
+    pub fn brdf(&self, base_color: &Color, normal: &Vector, to_viewer: &Vector, to_light: &Vector) -> Color {
+        let n_dot_l = normal.dot(to_light);
+        let n_dot_v = normal.dot(to_viewer);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+            return Color::black();
+        }
+        let half = to_viewer.plus(to_light).normalize();
+        let fresnel = self.fresnel(base_color, to_viewer.dot(&half));
+        let specular = fresnel.factor(self.distribution(normal.dot(&half)) * self.visibility(n_dot_l, n_dot_v));
+        let diffuse = Color::new(1.0, 1.0, 1.0).plus(&fresnel.factor(-1.0))
+            .times(base_color)
+            .factor((1.0 - self.metallic) / PI);
+        diffuse + specular
+    }
+    /// Probability of sampling the specular lobe rather than the diffuse one.
+    fn specular_probability(&self, base_color: &Color) -> f64 {
+        let specular = self.f0(base_color).luminance();
+        let diffuse = base_color.luminance() * (1.0 - self.metallic);
+        if specular + diffuse <= 0.0 {
+            return 1.0;
+        }
+        (specular / (specular + diffuse)).clamp(0.1, 1.0)
+    }
+    /// Density of `sample` returning `to_light`, with respect to solid angle.
+    pub fn pdf(&self, base_color: &Color, normal: &Vector, to_viewer: &Vector, to_light: &Vector) -> f64 {
+        let n_dot_l = normal.dot(to_light);
+        if n_dot_l <= 0.0 {
+            return 0.0;
+        }
+        let half = to_viewer.plus(to_light).normalize();
+        let v_dot_h = to_viewer.dot(&half);
+        let specular = if v_dot_h > 0.0 {
+            self.distribution(normal.dot(&half)) * normal.dot(&half) / (4.0 * v_dot_h)
+        } else {
+            0.0
+        };
+        let probability = self.specular_probability(base_color);
+        probability * specular + (1.0 - probability) * n_dot_l / PI
+    }
+    /// Picks the direction of incoming light for the random numbers `choice`, `u` and `v` in
+    /// `[0, 1)`, drawing half vectors from the GGX distribution or cosine weighted directions from
+    /// the diffuse base. `None` if the direction ends up below the surface.
+    pub fn sample(&self, base_color: &Color, normal: &Vector, to_viewer: &Vector, choice: f64, u: f64, v: f64)
+                  -> Option<MicrofacetSample> {
+        let to_light = if choice < self.specular_probability(base_color) {
+            let alpha2 = self.alpha() * self.alpha();
+            let cos_theta = ((1.0 - u) / (1.0 + (alpha2 - 1.0) * u)).sqrt();
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+            let (tangent, bitangent) = normal.orthonormal_basis();
+            let half = tangent.factor(sin_theta * phi.cos())
+                .plus(&bitangent.factor(sin_theta * phi.sin()))
+                .plus(&normal.factor(cos_theta));
+            half.factor(2.0 * to_viewer.dot(&half)).minus(to_viewer)
+        } else {
+            cosine_hemisphere(normal, u, v)
+        };
+        let pdf = self.pdf(base_color, normal, to_viewer, &to_light);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let weight = self.brdf(base_color, normal, to_viewer, &to_light).factor(normal.dot(&to_light) / pdf);
+        Some(MicrofacetSample {
+            direction: to_light,
+            weight: weight,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresnel() {
+        let plastic = Microfacet::new(0.0, 0.5, 1.0);
+        let gold = Color::new(1.0, 0.766, 0.336);
+        assert_eq!(plastic.fresnel(&gold, 1.0), Color::new(0.04, 0.04, 0.04));
+        assert_eq!(plastic.fresnel(&gold, 0.0), Color::new(1.0, 1.0, 1.0));
+        let metal = Microfacet::new(1.0, 0.5, 1.0);
+        assert_eq!(metal.fresnel(&gold, 1.0), gold);
+    }
+
+    #[test]
+    fn test_sampling() {
+        // the sampled estimate of the reflected fraction matches integrating the BRDF over the
+        // hemisphere, and never exceeds 1
+        let normal = Vector::new(0.0, 0.0, 1.0);
+        let to_viewer = Vector::new(0.6, 0.0, 0.8);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let steps = 200;
+        for &(metallic, roughness) in [(0.0, 0.3), (1.0, 0.4), (1.0, 0.9), (0.5, 0.6)].iter() {
+            let material = Microfacet::new(metallic, roughness, 1.0);
+            let mut sampled = 0.0;
+            let mut integrated = 0.0;
+            for i in 0..steps {
+                for j in 0..steps {
+                    let (u, v) = ((i as f64 + 0.5) / steps as f64, (j as f64 + 0.5) / steps as f64);
+                    let choice = ((i * steps + j) as f64 * 0.618_033_988_7).fract();
+                    if let Some(sample) = material.sample(&white, &normal, &to_viewer, choice, u, v) {
+                        sampled += sample.get_weight().get()[0];
+                    }
+                    // uniform in the cosine of the polar angle, so the solid angle of a cell is constant
+                    let (cos_theta, phi) = (u, 2.0 * PI * v);
+                    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                    let to_light = Vector::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                    integrated += material.brdf(&white, &normal, &to_viewer, &to_light).get()[0] * cos_theta * 2.0 * PI;
+                }
+            }
+            let (sampled, integrated) = (sampled / (steps * steps) as f64, integrated / (steps * steps) as f64);
+            assert!(sampled <= 1.0 && (sampled - integrated).abs() < 0.02, "{} {} {} {}", metallic, roughness,
+                    sampled, integrated);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::base::{Color, Point2D};
+use crate::vector::Vector;
+use crate::material::microfacet::Microfacet;
+
+pub mod image;
+pub mod microfacet;
+pub mod noise;
+
+/// Shadow rays sent towards glowing objects unless set otherwise.
+pub const DEFAULT_LIGHT_SAMPLES: u32 = 16;
+
+pub struct Material {
+    texture: Box<dyn Texture>,
+    albedo: f64,
+    glossiness: f64,
+    surface_type: SurfaceType,
+    emission: Color,
+    light_samples: u32,
+    specular: Color,
+    shininess: f64,
+}
+
+pub enum SurfaceType {
+    Diffuse,
+    Reflective { reflectivity: f64 },
+    /// Transparent surface refracting light by the refraction `index`. `transparency` blends
+    /// between the diffuse surface color (0) and the fully transmitted/reflected light (1).
+    Refractive { index: f64, transparency: f64 },
+    /// Physically based metallic-roughness surface, using the texture as its base colour.
+    Microfacet(Microfacet),
+}
+
+pub trait Texture: Send + Sync {
+    fn get_color(&self, x: f64, y: f64) -> Color;
+    /// Color at a hit point with the given texture coordinates. Solid textures override this to
+    /// look at the point in space rather than at the coordinates.
+    fn get_color_at(&self, _hit_point: &Vector, coords: &Point2D) -> Color {
+        self.get_color(coords.x, coords.y)
+    }
+}
+
+pub struct ConstantTexture {
+    color: Color,
+}
+
+impl ConstantTexture {
+    pub fn new(color: Color) -> ConstantTexture {
+        ConstantTexture {
+            color: color
+        }
+    }
+}
+
+impl Texture for ConstantTexture {
+    fn get_color(&self, _x: f64, _y: f64) -> Color {
+        self.color.clone()
+    }
+}
+
+impl Material {
+    pub fn new(texture: Box<dyn Texture>, surface_type: SurfaceType, albedo: f64, glossiness: f64) -> Material {
+        Material {
+            texture: texture,
+            albedo: albedo,
+            glossiness: glossiness,
+            surface_type: surface_type,
+            emission: Color::black(),
+            light_samples: DEFAULT_LIGHT_SAMPLES,
+            specular: Color::black(),
+            shininess: 0.0,
+        }
+    }
+    pub fn new_constant(color: Color, surface_type: SurfaceType, albedo: f64, glossiness: f64) -> Material {
+        Material {
+            texture: Box::new(ConstantTexture::new(color)),
+            albedo: albedo,
+            glossiness: glossiness,
+            surface_type: surface_type,
+            emission: Color::black(),
+            light_samples: DEFAULT_LIGHT_SAMPLES,
+            specular: Color::black(),
+            shininess: 0.0,
+        }
+    }
+    pub fn get_texture(&self) -> &dyn Texture {
+        self.texture.as_ref()
+    }
+    pub fn get_glossiness(&self) -> f64 {
+        self.glossiness
+    }
+    pub fn get_albedo(&self) -> f64 {
+        self.albedo
+    }
+    pub fn get_surface_type(&self) -> &SurfaceType { &self.surface_type }
+    /// Makes the surface glow with the given radiance, black by default. Glowing spheres, disks and
+    /// meshes also light the scene like area lights.
+    pub fn set_emission(&mut self, emission: Color) {
+        self.emission = emission;
+    }
+    pub fn get_emission(&self) -> &Color {
+        &self.emission
+    }
+    pub fn is_emissive(&self) -> bool {
+        self.emission.get().iter().any(|&component| component > 0.0)
+    }
+    /// Number of shadow rays sent towards the surface of a glowing object.
+    pub fn set_light_samples(&mut self, samples: u32) {
+        self.light_samples = samples;
+    }
+    pub fn get_light_samples(&self) -> u32 {
+        self.light_samples
+    }
+    /// Adds Blinn-Phong highlights of the given colour to the light reflected from scene lights,
+    /// narrower for a higher `shininess` exponent. Black by default, which turns them off.
+    pub fn set_specular(&mut self, specular: Color, shininess: f64) {
+        self.specular = specular;
+        self.shininess = shininess;
+    }
+    pub fn get_specular(&self) -> &Color {
+        &self.specular
+    }
+    pub fn get_shininess(&self) -> f64 {
+        self.shininess
+    }
+    /// Blinn-Phong highlight for light arriving from `to_light`, both directions pointing away
+    /// from the surface. Light from behind the surface leaves no highlight.
+    pub fn highlight(&self, normal: &Vector, to_viewer: &Vector, to_light: &Vector) -> Color {
+        if normal.dot(to_light) <= 0.0 {
+            return Color::black();
+        }
+        let half = to_viewer.plus(to_light).normalize();
+        self.specular.factor(normal.dot(&half).max(0.0).powf(self.shininess))
+    }
+    pub fn has_highlights(&self) -> bool {
+        self.specular != Color::black()
+    }
+}
+
+pub struct CheckeredPatternTexture {
+    color: Color,
+    width: u32,
+    height: u32,
+}
+
+impl CheckeredPatternTexture {
+    pub fn new(color: Color, width: u32, height: u32) -> CheckeredPatternTexture {
+        CheckeredPatternTexture {
+            color: color,
+            width: width,
+            height: height,
+        }
+    } }
+
+impl Texture for CheckeredPatternTexture {
+
+    fn get_color(&self, x: f64, y: f64) -> Color {
+        let cell_x = x.round() as u32 / self.width;
+        let cell_y = y.round() as u32 / self.height;
+
+        if (cell_x + cell_y).is_multiple_of(2) {
+            Color::new(0.007, 0.007, 0.007)
+        } else {
+            self.color.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let mut material = Material::new_constant(Color::new(1.0, 0.0, 0.0), SurfaceType::Diffuse, 1.0, 1.0);
+        material.set_specular(Color::new(0.5, 0.5, 0.5), 64.0);
+        // the highlight is brightest when the normal is halfway between viewer and light
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let to_light = Vector::new(1.0, 1.0, 0.0).normalize();
+        let mirrored = material.highlight(&normal, &Vector::new(-1.0, 1.0, 0.0).normalize(), &to_light);
+        let off = material.highlight(&normal, &Vector::new(0.0, 1.0, 0.0), &to_light);
+        assert_eq!(mirrored, Color::new(0.5, 0.5, 0.5));
+        assert!(off.get()[0] < 0.01);
+        // light grazing the surface from behind leaves no highlight
+        let behind = material.highlight(&normal, &normal, &Vector::new(10.0, -1.0, 0.0).normalize());
+        assert_eq!(behind, Color::black());
+    }
+}
@@ -0,0 +1,216 @@
+//! Procedural textures built on Perlin's improved gradient noise.
+
+use serde::Deserialize;
+
+use crate::base::{Color, Point2D};
+use crate::material::Texture;
+use crate::sampling::Rng;
+use crate::vector::Vector;
+
+/// Gradient noise over 3D space, smooth and repeating every 256 units.
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    /// Different seeds give unrelated noise, the same seed always the same one.
+    pub fn new(seed: u64) -> Perlin {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut rng = Rng::new(seed);
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i % 256];
+        }
+        Perlin {
+            permutation: permutation,
+        }
+    }
+    /// Noise value in about `[-1, 1]`, zero at integer coordinates.
+    pub fn noise(&self, point: &Vector) -> f64 {
+        let (x, y, z) = (point.get_x(), point.get_y(), point.get_z());
+        let (xf, yf, zf) = (x.floor(), y.floor(), z.floor());
+        let (xi, yi, zi) = ((xf as i64 & 255) as usize, (yf as i64 & 255) as usize, (zf as i64 & 255) as usize);
+        let (x, y, z) = (x - xf, y - yf, z - zf);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        lerp(w,
+             lerp(v,
+                  lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                  lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z))),
+             lerp(v,
+                  lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+                  lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0),
+                       gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))))
+    }
+    /// Fractal Brownian motion, `octaves` layers of noise each with double the frequency and half
+    /// the amplitude of the previous one.
+    pub fn fbm(&self, point: &Vector, octaves: u32) -> f64 {
+        self.octaves(point, octaves, |noise| noise)
+    }
+    /// Like `fbm`, but sums the absolute values, which gives sharp creases. Non-negative.
+    pub fn turbulence(&self, point: &Vector, octaves: u32) -> f64 {
+        self.octaves(point, octaves, f64::abs)
+    }
+    fn octaves<F: Fn(f64) -> f64>(&self, point: &Vector, octaves: u32, layer: F) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * layer(self.noise(&point.factor(frequency)));
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset with one of 12 gradients along the edges of a cube.
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Shape of the pattern a `NoiseTexture` draws.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoisePattern {
+    /// A single layer of noise.
+    Noise,
+    Fbm,
+    Turbulence,
+    /// Veins along the x axis, distorted by turbulence.
+    Marble,
+    /// Rings around the y axis, distorted by noise.
+    Wood,
+}
+
+/// Blends two colors by a noise pattern. The texture is solid: it is evaluated at the hit point in
+/// space, so it does not stretch or tear along texture seams, and objects look carved out of it.
+pub struct NoiseTexture {
+    perlin: Perlin,
+    pattern: NoisePattern,
+    low: Color,
+    high: Color,
+    scale: f64,
+    octaves: u32,
+    solid: bool,
+}
+
+impl NoiseTexture {
+    /// `scale` is the size of the features, `low` and `high` the colors at the extremes of the
+    /// pattern.
+    pub fn new(pattern: NoisePattern, low: Color, high: Color, scale: f64, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            perlin: Perlin::new(seed),
+            pattern: pattern,
+            low: low,
+            high: high,
+            scale: scale,
+            octaves: 6,
+            solid: true,
+        }
+    }
+    /// Number of noise layers of the fractal patterns, 6 by default.
+    pub fn set_octaves(&mut self, octaves: u32) {
+        self.octaves = octaves;
+    }
+    /// Evaluates the pattern at the texture coordinates instead of the hit point when `false`.
+    pub fn set_solid(&mut self, solid: bool) {
+        self.solid = solid;
+    }
+    /// Pattern value in `[0, 1]` at the point.
+    pub fn evaluate(&self, point: &Vector) -> f64 {
+        let p = point.factor(1.0 / self.scale);
+        let value = match self.pattern {
+            NoisePattern::Noise => 0.5 + 0.5 * self.perlin.noise(&p),
+            NoisePattern::Fbm => 0.5 + 0.5 * self.perlin.fbm(&p, self.octaves),
+            NoisePattern::Turbulence => self.perlin.turbulence(&p, self.octaves),
+            NoisePattern::Marble => {
+                let phase = p.get_x() + 5.0 * self.perlin.turbulence(&p, self.octaves);
+                0.5 + 0.5 * phase.sin()
+            }
+            NoisePattern::Wood => {
+                let radius = (p.get_x() * p.get_x() + p.get_z() * p.get_z()).sqrt();
+                let rings = 4.0 * (radius + 0.3 * self.perlin.fbm(&p, self.octaves));
+                rings - rings.floor()
+            }
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn get_color(&self, x: f64, y: f64) -> Color {
+        let t = self.evaluate(&Vector::new(x, y, 0.0));
+        self.low.factor(1.0 - t) + self.high.factor(t)
+    }
+
+    fn get_color_at(&self, hit_point: &Vector, coords: &Point2D) -> Color {
+        if !self.solid {
+            return self.get_color(coords.x, coords.y);
+        }
+        let t = self.evaluate(hit_point);
+        self.low.factor(1.0 - t) + self.high.factor(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_seeded() {
+        let point = Vector::new(1.3, 7.7, -2.1);
+        assert_eq!(Perlin::new(1).noise(&point), Perlin::new(1).noise(&point));
+        assert_ne!(Perlin::new(1).noise(&point), Perlin::new(2).noise(&point));
+        assert_eq!(Perlin::new(1).noise(&Vector::new(3.0, -4.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let perlin = Perlin::new(9);
+        let mut rng = Rng::new(4);
+        for _ in 0..1000 {
+            let point = Vector::new(rng.next_f64() * 20.0, rng.next_f64() * 20.0, rng.next_f64() * 20.0);
+            let value = perlin.noise(&point);
+            assert!((-1.0..=1.0).contains(&value));
+            let nearby = perlin.noise(&point.plus(&Vector::new(1e-6, 1e-6, 1e-6)));
+            assert!((value - nearby).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_patterns_stay_in_range() {
+        let mut rng = Rng::new(8);
+        for pattern in [NoisePattern::Noise, NoisePattern::Fbm, NoisePattern::Turbulence, NoisePattern::Marble,
+                        NoisePattern::Wood].iter() {
+            let texture = NoiseTexture::new(*pattern, Color::black(), Color::new(1.0, 1.0, 1.0), 0.5, 3);
+            for _ in 0..100 {
+                let point = Vector::new(rng.next_f64() * 10.0, rng.next_f64() * 10.0, rng.next_f64() * 10.0);
+                assert!((0.0..=1.0).contains(&texture.evaluate(&point)));
+            }
+        }
+    }
+}
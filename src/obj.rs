@@ -0,0 +1,418 @@
+//! Loader for Wavefront `.obj` meshes and their `.mtl` material libraries.
+//!
+//! Every material group (and every `o`/`g` statement) of an `.obj` file becomes a separate `Mesh`,
+//! holding only the vertices its faces reference. Polygons are triangulated as fans.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::base::{Color, Point2D};
+use crate::material::{Material, SurfaceType};
+use crate::objects::mesh::{Face, Mesh};
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    /// Reading failed in one of the parsers, which do not know the path of what they read.
+    Read(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl ObjError {
+    /// Attaches the path of the file that was being read to a `Read` error.
+    fn in_file(self, path: &Path) -> ObjError {
+        match self {
+            ObjError::Read(error) => ObjError::Io(path.to_path_buf(), error),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(path, error) => write!(f, "cannot read {}: {}", path.display(), error),
+            ObjError::Read(error) => write!(f, "cannot read: {}", error),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Material properties read from a `.mtl` file, turned into a `Material` for every mesh using it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct MtlMaterial {
+    pub diffuse: [f64; 3],
+    pub specular: [f64; 3],
+    pub shininess: f64,
+    pub emission: [f64; 3],
+    pub illumination: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [0.0, 0.0, 0.0],
+            shininess: 10.0,
+            emission: [0.0, 0.0, 0.0],
+            illumination: 1,
+        }
+    }
+}
+
+impl MtlMaterial {
+    pub fn to_material(&self) -> Material {
+        let surface_type = if self.illumination == 3 {
+            SurfaceType::Reflective { reflectivity: self.specular.iter().cloned().fold(0.0, f64::max) }
+        } else {
+            SurfaceType::Diffuse
+        };
+        let mut material = Material::new_constant(Color::from_array(self.diffuse), surface_type, 1.0, 1.0);
+        // illumination model 2 is diffuse shading with highlights
+        if self.illumination == 2 {
+            material.set_specular(Color::from_array(self.specular), self.shininess);
+        }
+        material.set_emission(Color::from_array(self.emission));
+        material
+    }
+}
+
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Vec<Mesh>, ObjError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_obj(BufReader::new(file), base_dir).map_err(|e| e.in_file(path))
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+    parse_mtl(BufReader::new(file)).map_err(|e| e.in_file(path))
+}
+
+/// Parses an `.obj` file, resolving `mtllib` statements relative to `base_dir`.
+pub fn parse_obj<R: BufRead>(reader: R, base_dir: &Path) -> Result<Vec<Mesh>, ObjError> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut materials = HashMap::new();
+
+    let mut meshes = Vec::new();
+    let mut group = Group::new(MtlMaterial::default());
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(ObjError::Read)?;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_vector(&args, line_number)?),
+            "vn" => normals.push(parse_vector(&args, line_number)?),
+            "vt" => {
+                let coords = parse_floats(&args, line_number)?;
+                if coords.is_empty() {
+                    return Err(parse_error(line_number, "texture coordinate has no components"));
+                }
+                uvs.push(Point2D { x: coords[0], y: coords.get(1).cloned().unwrap_or(0.0) });
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(parse_error(line_number, "face needs at least 3 vertices"));
+                }
+                let vertices = args.iter()
+                    .map(|arg| parse_vertex(arg, positions.len(), uvs.len(), normals.len(), line_number))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for i in 1..vertices.len() - 1 {
+                    group.add_face([&vertices[0], &vertices[i], &vertices[i + 1]],
+                                   &positions, &uvs, &normals);
+                }
+            }
+            "usemtl" => {
+                let name = args.first().ok_or_else(|| parse_error(line_number, "usemtl without a name"))?;
+                let material = materials.get(*name).cloned()
+                    .ok_or_else(|| parse_error(line_number, &format!("unknown material {}", name)))?;
+                group.flush(&mut meshes);
+                group = Group::new(material);
+            }
+            "mtllib" => {
+                for library in args {
+                    materials.extend(load_mtl(base_dir.join(library))?);
+                }
+            }
+            "o" | "g" => {
+                let material = group.material.clone();
+                group.flush(&mut meshes);
+                group = Group::new(material);
+            }
+            _ => {}
+        }
+    }
+    group.flush(&mut meshes);
+    Ok(meshes)
+}
+
+pub fn parse_mtl<R: BufRead>(reader: R) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(ObjError::Read)?;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = args.first().ok_or_else(|| parse_error(line_number, "newmtl without a name"))?;
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+            current = Some((name.to_string(), MtlMaterial::default()));
+            continue;
+        }
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => continue,
+        };
+        match keyword {
+            "Kd" => material.diffuse = parse_rgb(&args, line_number)?,
+            "Ks" => material.specular = parse_rgb(&args, line_number)?,
+            "Ke" => material.emission = parse_rgb(&args, line_number)?,
+            "Ns" => {
+                material.shininess = args.first()
+                    .and_then(|arg| arg.parse().ok())
+                    .ok_or_else(|| parse_error(line_number, "invalid specular exponent"))?;
+            }
+            "illum" => {
+                material.illumination = args.first()
+                    .and_then(|arg| arg.parse().ok())
+                    .ok_or_else(|| parse_error(line_number, "invalid illumination model"))?;
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+    Ok(materials)
+}
+
+/// Faces of the current group, re-indexed into vertex lists holding only what the group uses.
+struct Group {
+    material: MtlMaterial,
+    positions: Vec<Vector>,
+    normals: Vec<Vector>,
+    uvs: Vec<Point2D>,
+    faces: Vec<Face>,
+    position_indices: HashMap<usize, usize>,
+    normal_indices: HashMap<usize, usize>,
+    uv_indices: HashMap<usize, usize>,
+}
+
+struct Vertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+impl Group {
+    fn new(material: MtlMaterial) -> Group {
+        Group {
+            material: material,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            faces: Vec::new(),
+            position_indices: HashMap::new(),
+            normal_indices: HashMap::new(),
+            uv_indices: HashMap::new(),
+        }
+    }
+    fn add_face(&mut self, vertices: [&Vertex; 3], positions: &[Vector], uvs: &[Point2D], normals: &[Vector]) {
+        let mut face_positions = [0; 3];
+        let mut face_uvs = [0; 3];
+        let mut face_normals = [0; 3];
+        for (i, vertex) in vertices.iter().enumerate() {
+            face_positions[i] = local_index(vertex.position, positions, &mut self.positions,
+                                            &mut self.position_indices);
+            if let Some(uv) = vertex.uv {
+                face_uvs[i] = local_index(uv, uvs, &mut self.uvs, &mut self.uv_indices);
+            }
+            if let Some(normal) = vertex.normal {
+                face_normals[i] = local_index(normal, normals, &mut self.normals, &mut self.normal_indices);
+            }
+        }
+        self.faces.push(Face {
+            positions: face_positions,
+            uvs: if vertices.iter().all(|v| v.uv.is_some()) { Some(face_uvs) } else { None },
+            normals: if vertices.iter().all(|v| v.normal.is_some()) { Some(face_normals) } else { None },
+        });
+    }
+    fn flush(self, meshes: &mut Vec<Mesh>) {
+        if !self.faces.is_empty() {
+            meshes.push(Mesh::new(self.positions, self.normals, self.uvs, self.faces,
+                                  self.material.to_material()));
+        }
+    }
+}
+
+fn local_index<T: Clone>(global: usize, source: &[T], target: &mut Vec<T>,
+                         indices: &mut HashMap<usize, usize>) -> usize {
+    *indices.entry(global).or_insert_with(|| {
+        target.push(source[global].clone());
+        target.len() - 1
+    })
+}
+
+fn parse_error(line: usize, message: &str) -> ObjError {
+    ObjError::Parse { line: line, message: message.to_string() }
+}
+
+fn parse_floats(args: &[&str], line: usize) -> Result<Vec<f64>, ObjError> {
+    args.iter()
+        .map(|arg| arg.parse::<f64>().map_err(|_| parse_error(line, &format!("invalid number {}", arg))))
+        .collect()
+}
+
+fn parse_vector(args: &[&str], line: usize) -> Result<Vector, ObjError> {
+    let coords = parse_floats(args, line)?;
+    if coords.len() < 3 {
+        return Err(parse_error(line, "expected 3 coordinates"));
+    }
+    Ok(Vector::new(coords[0], coords[1], coords[2]))
+}
+
+fn parse_rgb(args: &[&str], line: usize) -> Result<[f64; 3], ObjError> {
+    let rgb = parse_floats(args, line)?;
+    match rgb.len() {
+        1 => Ok([rgb[0], rgb[0], rgb[0]]),
+        3 => Ok([rgb[0], rgb[1], rgb[2]]),
+        _ => Err(parse_error(line, "expected 1 or 3 color components")),
+    }
+}
+
+/// Resolves a 1-based (or negative, relative to the end) index into a 0-based one.
+fn resolve_index(index: &str, count: usize, line: usize) -> Result<usize, ObjError> {
+    let value: i64 = index.parse().map_err(|_| parse_error(line, &format!("invalid index {}", index)))?;
+    let resolved = if value < 0 { count as i64 + value } else { value - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(parse_error(line, &format!("index {} out of range", value)));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_vertex(arg: &str, positions: usize, uvs: usize, normals: usize, line: usize) -> Result<Vertex, ObjError> {
+    let mut parts = arg.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), positions, line)?;
+    let uv = match parts.next() {
+        Some(uv) if !uv.is_empty() => Some(resolve_index(uv, uvs, line)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(normal) if !normal.is_empty() => Some(resolve_index(normal, normals, line)?),
+        _ => None,
+    };
+    Ok(Vertex {
+        position: position,
+        uv: uv,
+        normal: normal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "
+# a unit quad split by material
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1
+o second
+f -4 -2 -1
+";
+
+    #[test]
+    fn test_parse_obj() {
+        let meshes = parse_obj(QUAD.as_bytes(), Path::new("")).unwrap();
+        assert_eq!(meshes.len(), 2);
+
+        let first = &meshes[0];
+        assert_eq!(first.get_faces()[0], Face {
+            positions: [0, 1, 2],
+            normals: Some([0, 0, 0]),
+            uvs: Some([0, 1, 2]),
+        });
+
+        let second = &meshes[1];
+        assert_eq!(second.get_faces()[0], Face { positions: [0, 1, 2], normals: None, uvs: None });
+        assert_eq!(second.get_position(2), &Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_fan_triangulation() {
+        let meshes = parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n".as_bytes(),
+                               Path::new("")).unwrap();
+        assert_eq!(meshes[0].get_faces().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let result = parse_obj("v 0 0 0\nf 1 2 3\n".as_bytes(), Path::new(""));
+        match result {
+            Err(ObjError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let materials = parse_mtl("newmtl red\nKd 1 0 0\nKe 4 2 0\nnewmtl mirror\nKs 0.5\nillum 3\n".as_bytes()).unwrap();
+        assert_eq!(materials["red"].diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(materials["red"].to_material().get_emission(), &Color::new(4.0, 2.0, 0.0));
+        assert_eq!(materials["mirror"].specular, [0.5, 0.5, 0.5]);
+        assert_eq!(materials["mirror"].illumination, 3);
+    }
+
+    #[test]
+    fn test_read_error_names_the_file() {
+        let path = std::env::temp_dir().join(format!("rustracer-invalid-{}.mtl", std::process::id()));
+        std::fs::write(&path, b"newmtl \xff\xfe\n").unwrap();
+        let result = load_mtl(&path);
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(ObjError::Io(error_path, _)) => assert_eq!(error_path, path),
+            _ => panic!("expected a read error"),
+        }
+        assert!(matches!(parse_mtl(&b"newmtl \xff\n"[..]), Err(ObjError::Read(_))));
+    }
+
+    #[test]
+    fn test_highlights() {
+        let materials = parse_mtl("newmtl plastic\nKd 1 0 0\nKs 0.5 0.5 0.5\nNs 64\nillum 2\n".as_bytes()).unwrap();
+        let material = materials["plastic"].to_material();
+        assert_eq!(material.get_specular(), &Color::new(0.5, 0.5, 0.5));
+        assert_eq!(material.get_shininess(), 64.0);
+        assert!(material.has_highlights());
+    }
+}
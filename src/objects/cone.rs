@@ -0,0 +1,194 @@
+use crate::vector::Vector;
+use crate::base::{BoundingBox, Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use std::f64::consts::PI;
+use crate::material::Material;
+use crate::objects::polynomial::solve_quadratic;
+use crate::objects::{convex_interval, pick_by_area, Roots, MIN_DISTANCE};
+
+/// Closed upright cone standing on the centre of its base, with the apex `height` above it.
+pub struct Cone {
+    center: Vector,
+    radius: f64,
+    height: f64,
+    material: Material,
+}
+
+impl Cone {
+    pub fn new(center: Vector, radius: f64, height: f64, material: Material) -> Cone {
+        Cone {
+            center: center,
+            radius: radius,
+            height: height,
+            material: material,
+        }
+    }
+    pub fn get_center(&self) -> &Vector {
+        &self.center
+    }
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+    pub fn get_height(&self) -> f64 {
+        self.height
+    }
+    /// Sorted distances at which the ray crosses the surface.
+    pub fn crossings(&self, ray: &Ray) -> Roots {
+        let origin = ray.get_origin().minus(&self.center);
+        let direction = ray.get_direction();
+        let (ox, oy, oz) = (origin.get_x(), origin.get_y(), origin.get_z());
+        let (dx, dy, dz) = (direction.get_x(), direction.get_y(), direction.get_z());
+
+        // x² + z² = k² (height - y)², with the slope k of the side
+        let k2 = (self.radius / self.height).powi(2);
+        let h = self.height - oy;
+        let mut crossings: Roots = solve_quadratic(dx * dx + dz * dz - k2 * dy * dy,
+                                                   2.0 * (ox * dx + oz * dz + k2 * h * dy),
+                                                   ox * ox + oz * oz - k2 * h * h)
+            .into_iter()
+            .filter(|t| (0.0..=self.height).contains(&(oy + t * dy)))
+            .collect();
+        if dy != 0.0 {
+            let t = -oy / dy;
+            let (x, z) = (ox + t * dx, oz + t * dz);
+            if x * x + z * z <= self.radius * self.radius {
+                crossings.push(t);
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        crossings.dedup();
+        crossings
+    }
+}
+
+impl Intersectable for Cone {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.crossings(ray).into_iter().find(|&t| t > MIN_DISTANCE)
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let local = hit_point.minus(&self.center);
+        let distance = (local.get_x().powi(2) + local.get_z().powi(2)).sqrt();
+        let slope = self.radius / self.height;
+        let side_distance = (distance - slope * (self.height - local.get_y())).abs() / (1.0 + slope * slope).sqrt();
+        if local.get_y().abs() < side_distance {
+            return Vector::new(0.0, -1.0, 0.0);
+        }
+        if distance == 0.0 {
+            // the apex
+            return Vector::new(0.0, 1.0, 0.0);
+        }
+        Vector::new(local.get_x(), slope * distance, local.get_z()).normalize()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vector::new(self.radius, 0.0, self.radius);
+        BoundingBox::new(self.center.minus(&extent),
+                         self.center.plus(&extent).plus(&Vector::new(0.0, self.height, 0.0)))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        convex_interval(&self.crossings(ray))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(PI * self.radius * (self.radius.hypot(self.height) + self.radius))
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        let base = PI * self.radius * self.radius;
+        let (part, u) = pick_by_area(&[PI * self.radius * self.radius.hypot(self.height), base], u);
+        let phi = 2.0 * PI * v;
+        let (cos, sin) = (phi.cos(), phi.sin());
+        // the circles around the apex and the base grow with their radius, so both are sampled alike
+        let r = self.radius * u.sqrt();
+        let (local, normal) = if part == 0 {
+            let slope = self.radius / self.height;
+            (Vector::new(r * cos, self.height * (1.0 - u.sqrt()), r * sin), Vector::new(cos, slope, sin).normalize())
+        } else {
+            (Vector::new(r * cos, 0.0, r * sin), Vector::new(0.0, -1.0, 0.0))
+        };
+        Some((self.center.plus(&local), normal))
+    }
+}
+
+impl Drawable for Cone {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for Cone {
+    /// Wraps the texture around the side once, starting at the base.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let hit_vec = hit_point.minus(&self.center);
+        let phi = hit_vec.get_z().atan2(hit_vec.get_x());
+        Point2D {
+            x: (1.0 + phi / PI) * 0.5,
+            y: (hit_vec.get_y() / self.height).clamp(0.0, 1.0),
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::assert_samples_on_surface;
+
+    fn cone() -> Cone {
+        Cone::new(Vector::zero(), 1.0, 2.0,
+                  Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_intersect() {
+        let cone = cone();
+        // halfway up the radius is 0.5
+        let side = Ray::from(Vector::new(0.0, 1.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cone.intersect(&side), Some(4.5));
+        let normal = cone.surface_normal(&Vector::new(0.0, 1.0, 0.5));
+        assert!(normal.minus(&Vector::new(0.0, 0.5, 1.0).normalize()).euclidian_distance() < 1e-12);
+
+        let base = Ray::from(Vector::new(0.5, -3.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(cone.intersect(&base), Some(3.0));
+        assert_eq!(cone.surface_normal(&Vector::new(0.5, 0.0, 0.0)), Vector::new(0.0, -1.0, 0.0));
+
+        // the other nappe of the double cone above the apex is not part of it
+        let above = Ray::from(Vector::new(0.0, 3.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cone.intersect(&above), None);
+    }
+
+    #[test]
+    fn test_from_inside() {
+        let cone = cone();
+        let up = Ray::from(Vector::new(0.0, 0.5, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert!((cone.intersect(&up).unwrap() - 1.5).abs() < 1e-9);
+        let down = Ray::from(Vector::new(0.0, 0.5, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(cone.intersect(&down), Some(0.5));
+    }
+
+    #[test]
+    fn test_sampled_surface() {
+        let cone = cone();
+        assert!((cone.surface_area().unwrap() - PI * (5f64.sqrt() + 1.0)).abs() < 1e-12);
+        assert_samples_on_surface(&cone);
+    }
+
+    #[test]
+    fn test_grazing() {
+        let cone = cone();
+        // parallel to one side, so the quadratic degenerates, entering through the other one
+        let parallel = Ray::from(Vector::new(0.0, 2.0, -0.5), Vector::new(0.0, -2.0, 1.0).normalize());
+        let crossings = cone.crossings(&parallel);
+        assert_eq!(crossings.len(), 2);
+        assert!((crossings[0] * 2.0 / 5f64.sqrt() - 0.5).abs() < 1e-9);
+        // touching the apex
+        let apex = Ray::from(Vector::new(-5.0, 2.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(cone.intersect(&apex).is_some_and(|t| (t - 5.0).abs() < 1e-6));
+    }
+}
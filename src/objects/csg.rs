@@ -0,0 +1,242 @@
+use serde::Deserialize;
+
+use crate::base::{BoundingBox, Color, Drawable, Intersectable, Intersection, Point2D, Ray, Textureable};
+use crate::material::Material;
+use crate::objects::MIN_DISTANCE;
+use crate::vector::Vector;
+
+/// Boolean operation combining the solids of a `Csg` node.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Union,
+    Intersection,
+    /// The left solid with the right one cut out of it.
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Operation::Union => in_left || in_right,
+            Operation::Intersection => in_left && in_right,
+            Operation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Place where the ray enters or leaves the combined solid, on the surface of one of the children.
+struct Boundary {
+    distance: f64,
+    side: Side,
+}
+
+/// Constructive solid geometry node combining two solids. The children have to report their
+/// `intervals`, hits are on the surface of a child and use its material. Asked for its surface
+/// directly, the node answers with the child whose bounds are nearest to the point, the left one
+/// where both are equally near, and with the material of the left child.
+pub struct Csg {
+    operation: Operation,
+    left: Box<dyn Drawable>,
+    right: Box<dyn Drawable>,
+    bounds: BoundingBox,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: Box<dyn Drawable>, right: Box<dyn Drawable>) -> Csg {
+        let bounds = match operation {
+            Operation::Union => left.bounding_box().union(&right.bounding_box()),
+            Operation::Intersection => left.bounding_box().overlap(&right.bounding_box()),
+            Operation::Difference => left.bounding_box(),
+        };
+        Csg {
+            operation: operation,
+            left: left,
+            right: right,
+            bounds: bounds,
+        }
+    }
+    pub fn get_operation(&self) -> Operation {
+        self.operation
+    }
+    /// Entries and exits of the combined solid in order, found by walking along the ends of the
+    /// spans of both children.
+    fn boundaries(&self, ray: &Ray) -> Vec<Boundary> {
+        let mut events = Vec::new();
+        for &(side, child) in [(Side::Left, &self.left), (Side::Right, &self.right)].iter() {
+            for (entry, exit) in child.intervals(ray) {
+                events.push((entry, side, true));
+                events.push((exit, side, false));
+            }
+        }
+        // entries go first at equal distances, so solids touching each other merge without a gap
+        events.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal).then(b.2.cmp(&a.2)));
+
+        let (mut in_left, mut in_right, mut inside) = (false, false, false);
+        let mut boundaries = Vec::new();
+        for (distance, side, entering) in events {
+            match side {
+                Side::Left => in_left = entering,
+                Side::Right => in_right = entering,
+            }
+            if self.operation.contains(in_left, in_right) != inside {
+                inside = !inside;
+                boundaries.push(Boundary { distance: distance, side: side });
+            }
+        }
+        boundaries
+    }
+    fn child(&self, side: Side) -> &dyn Drawable {
+        match side {
+            Side::Left => self.left.as_ref(),
+            Side::Right => self.right.as_ref(),
+        }
+    }
+    fn side_at(&self, point: &Vector) -> Side {
+        if self.right.bounding_box().distance(point) < self.left.bounding_box().distance(point) {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+    fn hit_boundary(&self, ray: &Ray, boundary: &Boundary) -> Intersection<'_> {
+        let intersection = self.child(boundary.side).hit_at(ray, boundary.distance);
+        // the surface of a cut out solid faces into what remains
+        if self.operation == Operation::Difference && boundary.side == Side::Right {
+            intersection.inverted()
+        } else {
+            intersection
+        }
+    }
+}
+
+impl Intersectable for Csg {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.boundaries(ray).into_iter()
+            .map(|boundary| boundary.distance)
+            .find(|&distance| distance > MIN_DISTANCE)
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let side = self.side_at(hit_point);
+        let normal = self.child(side).surface_normal(hit_point);
+        if self.operation == Operation::Difference && side == Side::Right {
+            normal.neg()
+        } else {
+            normal
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        self.boundaries(ray).chunks_exact(2)
+            .map(|pair| (pair[0].distance, pair[1].distance))
+            .collect()
+    }
+}
+
+impl Textureable for Csg {
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        self.child(self.side_at(hit_point)).texture_coords(hit_point)
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        self.child(self.side_at(hit_point)).get_texture_color(hit_point)
+    }
+}
+
+impl Drawable for Csg {
+    fn get_material(&self) -> &Material {
+        self.left.get_material()
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let boundary = self.boundaries(ray).into_iter().find(|boundary| boundary.distance > MIN_DISTANCE)?;
+        Some(self.hit_boundary(ray, &boundary))
+    }
+
+    fn hit_at(&self, ray: &Ray, distance: f64) -> Intersection<'_> {
+        let boundary = self.boundaries(ray).into_iter()
+            .min_by(|a, b| (a.distance - distance).abs().partial_cmp(&(b.distance - distance).abs())
+                .unwrap_or(std::cmp::Ordering::Equal))
+            .expect("distance is on the surface");
+        self.hit_boundary(ray, &boundary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::cuboid::Cuboid;
+    use crate::objects::sphere::Sphere;
+
+    fn sphere(x: f64, albedo: f64) -> Box<dyn Drawable> {
+        let material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, albedo, 1.0);
+        Box::new(Sphere::new(Vector::new(x, 0.0, 0.0), 1.0, material))
+    }
+
+    fn ray() -> Ray {
+        Ray::from(Vector::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn test_operations() {
+        // spheres spanning x in [-1.5, 0.5] and [-0.5, 1.5]
+        let union = Csg::new(Operation::Union, sphere(-0.5, 0.1), sphere(0.5, 0.2));
+        assert_eq!(union.intervals(&ray()), vec![(3.5, 6.5)]);
+
+        let intersection = Csg::new(Operation::Intersection, sphere(-0.5, 0.1), sphere(0.5, 0.2));
+        assert_eq!(intersection.intervals(&ray()), vec![(4.5, 5.5)]);
+        // entering through the right sphere
+        let hit = intersection.hit(&ray()).unwrap();
+        assert_eq!(hit.get_object().get_material().get_albedo(), 0.2);
+
+        let difference = Csg::new(Operation::Difference, sphere(-0.5, 0.1), sphere(0.5, 0.2));
+        assert_eq!(difference.intervals(&ray()), vec![(3.5, 4.5)]);
+        let back = Ray::from(Vector::new(5.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        let hit = difference.hit(&back).unwrap();
+        assert_eq!(hit.get_distance(), 5.5);
+        assert_eq!(hit.get_object().get_material().get_albedo(), 0.2);
+        // the carved surface faces the ray
+        assert_eq!(hit.surface_normal(&Vector::new(-0.5, 0.0, 0.0)), Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_hollow() {
+        let material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        let cube = Box::new(Cuboid::new(Vector::new(-2.0, -2.0, -2.0), Vector::new(2.0, 2.0, 2.0), material));
+        let hollow = Csg::new(Operation::Difference, cube, sphere(0.0, 0.5));
+        // from the center the ray leaves the hole through the sphere
+        let inside = Ray::from(Vector::zero(), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(hollow.intersect(&inside), Some(1.0));
+        // through a corner of the cube the sphere is missed
+        let corner = Ray::from(Vector::new(-5.0, 0.9, 0.9), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(hollow.intervals(&corner), vec![(3.0, 7.0)]);
+
+        let nested = Csg::new(Operation::Union, Box::new(hollow), sphere(0.0, 0.2));
+        assert_eq!(nested.intervals(&ray()), vec![(3.0, 7.0)]);
+        assert_eq!(nested.hit_at(&ray(), 3.0).get_object().get_material().get_albedo(), 1.0);
+    }
+
+    #[test]
+    fn test_surface() {
+        // spheres spanning x in [-3, -1] and [1, 3], the node answers for the one at the point
+        let union = Csg::new(Operation::Union, sphere(-2.0, 0.1), sphere(2.0, 0.2));
+        assert_eq!(union.surface_normal(&Vector::new(-3.0, 0.0, 0.0)), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(union.surface_normal(&Vector::new(3.0, 0.0, 0.0)), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(union.get_material().get_albedo(), 0.1);
+
+        let difference = Csg::new(Operation::Difference, sphere(-2.0, 0.1), sphere(2.0, 0.2));
+        assert_eq!(difference.surface_normal(&Vector::new(1.0, 0.0, 0.0)), Vector::new(1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::vector::Vector;
+use crate::base::{BoundingBox, Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use crate::material::Material;
+use crate::objects::{convex_interval, pick_by_area, Roots, MIN_DISTANCE};
+
+/// Axis aligned box between two corners.
+pub struct Cuboid {
+    min: Vector,
+    max: Vector,
+    material: Material,
+}
+
+impl Cuboid {
+    pub fn new(min: Vector, max: Vector, material: Material) -> Cuboid {
+        Cuboid {
+            min: min.min(&max),
+            max: min.max(&max),
+            material: material,
+        }
+    }
+    pub fn get_min(&self) -> &Vector {
+        &self.min
+    }
+    pub fn get_max(&self) -> &Vector {
+        &self.max
+    }
+    /// Distances at which the ray enters and leaves the box, if it crosses it.
+    pub fn crossings(&self, ray: &Ray) -> Roots {
+        let mut near = f64::NEG_INFINITY;
+        let mut far = f64::INFINITY;
+        for axis in 0..3 {
+            let origin = ray.get_origin().get_axis(axis);
+            let direction = ray.get_direction().get_axis(axis);
+            let (min, max) = (self.min.get_axis(axis), self.max.get_axis(axis));
+            if direction == 0.0 {
+                if origin < min || origin > max {
+                    return Roots::new();
+                }
+                continue;
+            }
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        if near > far {
+            return Roots::new();
+        }
+        Roots::from_slice(&[near, far])
+    }
+    /// Axis of the face the point lies on, and whether it is the face at the larger coordinate.
+    fn face(&self, hit_point: &Vector) -> (usize, bool) {
+        let center = self.min.plus(&self.max).factor(0.5);
+        let half_size = self.max.minus(&self.min).factor(0.5);
+        (0..3)
+            .map(|axis| {
+                let offset = (hit_point.get_axis(axis) - center.get_axis(axis)) / half_size.get_axis(axis);
+                (axis, offset)
+            })
+            .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(axis, offset)| (axis, offset > 0.0))
+            .unwrap()
+    }
+    /// Areas of the faces, the one at the smaller and the one at the larger coordinate of each axis.
+    fn face_areas(&self) -> [f64; 6] {
+        let extent = self.max.minus(&self.min);
+        [0, 0, 1, 1, 2, 2].map(|axis| extent.get_axis((axis + 1) % 3) * extent.get_axis((axis + 2) % 3))
+    }
+}
+
+impl Intersectable for Cuboid {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.crossings(ray).into_iter().find(|&t| t > MIN_DISTANCE)
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let (axis, positive) = self.face(hit_point);
+        let sign = if positive { 1.0 } else { -1.0 };
+        let mut normal = [0.0; 3];
+        normal[axis] = sign;
+        Vector::from_array(normal)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(self.min.clone(), self.max.clone())
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(self.face_areas().iter().sum())
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        let (face, u) = pick_by_area(&self.face_areas(), u);
+        let (axis, positive) = (face / 2, face % 2 == 1);
+        let mut point = [0.0; 3];
+        let mut normal = [0.0; 3];
+        point[axis] = if positive { self.max.get_axis(axis) } else { self.min.get_axis(axis) };
+        normal[axis] = if positive { 1.0 } else { -1.0 };
+        for (other, t) in [((axis + 1) % 3, u), ((axis + 2) % 3, v)] {
+            point[other] = self.min.get_axis(other) + t * (self.max.get_axis(other) - self.min.get_axis(other));
+        }
+        Some((Vector::from_array(point), Vector::from_array(normal)))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        convex_interval(&self.crossings(ray))
+    }
+}
+
+impl Drawable for Cuboid {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for Cuboid {
+    /// Every face is mapped to the unit square, upright on the side faces.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let relative = |axis: usize| {
+            (hit_point.get_axis(axis) - self.min.get_axis(axis)) / (self.max.get_axis(axis) - self.min.get_axis(axis))
+        };
+        match self.face(hit_point).0 {
+            0 => Point2D { x: relative(2), y: 1.0 - relative(1) },
+            1 => Point2D { x: relative(0), y: relative(2) },
+            _ => Point2D { x: relative(0), y: 1.0 - relative(1) },
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::assert_samples_on_surface;
+
+    fn cuboid() -> Cuboid {
+        Cuboid::new(Vector::new(1.0, 1.0, 1.0), Vector::new(-1.0, -1.0, -1.0),
+                    Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_intersect() {
+        let cuboid = cuboid();
+        let ray = Ray::from(Vector::new(0.5, 0.5, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cuboid.intersect(&ray), Some(4.0));
+        assert_eq!(cuboid.surface_normal(&Vector::new(0.5, 0.5, 1.0)), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(cuboid.texture_coords(&Vector::new(0.5, 0.5, 1.0)), Point2D { x: 0.75, y: 0.25 });
+
+        // from the inside the far side is hit
+        let inside = Ray::from(Vector::zero(), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(cuboid.intersect(&inside), Some(1.0));
+        assert_eq!(cuboid.surface_normal(&Vector::new(-1.0, 0.2, 0.3)), Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sampled_surface() {
+        let cuboid = Cuboid::new(Vector::zero(), Vector::new(1.0, 2.0, 3.0),
+                                 Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0));
+        assert_eq!(cuboid.surface_area(), Some(22.0));
+        assert_samples_on_surface(&cuboid);
+    }
+
+    #[test]
+    fn test_grazing() {
+        let cuboid = cuboid();
+        // sliding along the top face still touches the box, just above it does not
+        let along_face = Ray::from(Vector::new(-5.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(cuboid.intersect(&along_face), Some(4.0));
+        let above = Ray::from(Vector::new(-5.0, 1.0 + 1e-9, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(cuboid.intersect(&above), None);
+        let behind = Ray::from(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(cuboid.intersect(&behind), None);
+    }
+}
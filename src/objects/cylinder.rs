@@ -0,0 +1,184 @@
+use crate::vector::Vector;
+use crate::base::{BoundingBox, Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use std::f64::consts::PI;
+use crate::material::Material;
+use crate::objects::polynomial::solve_quadratic;
+use crate::objects::{convex_interval, pick_by_area, Roots, MIN_DISTANCE};
+
+/// Closed upright cylinder standing on the centre of its bottom cap.
+pub struct Cylinder {
+    center: Vector,
+    radius: f64,
+    height: f64,
+    material: Material,
+}
+
+impl Cylinder {
+    pub fn new(center: Vector, radius: f64, height: f64, material: Material) -> Cylinder {
+        Cylinder {
+            center: center,
+            radius: radius,
+            height: height,
+            material: material,
+        }
+    }
+    pub fn get_center(&self) -> &Vector {
+        &self.center
+    }
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+    pub fn get_height(&self) -> f64 {
+        self.height
+    }
+    /// Sorted distances at which the ray crosses the surface.
+    pub fn crossings(&self, ray: &Ray) -> Roots {
+        let origin = ray.get_origin().minus(&self.center);
+        let direction = ray.get_direction();
+        let (ox, oy, oz) = (origin.get_x(), origin.get_y(), origin.get_z());
+        let (dx, dy, dz) = (direction.get_x(), direction.get_y(), direction.get_z());
+
+        let mut crossings: Roots = solve_quadratic(dx * dx + dz * dz, 2.0 * (ox * dx + oz * dz),
+                                                   ox * ox + oz * oz - self.radius * self.radius)
+            .into_iter()
+            .filter(|t| (0.0..=self.height).contains(&(oy + t * dy)))
+            .collect();
+        if dy != 0.0 {
+            for &cap in [0.0, self.height].iter() {
+                let t = (cap - oy) / dy;
+                let (x, z) = (ox + t * dx, oz + t * dz);
+                if x * x + z * z <= self.radius * self.radius {
+                    crossings.push(t);
+                }
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        crossings
+    }
+}
+
+impl Intersectable for Cylinder {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.crossings(ray).into_iter().find(|&t| t > MIN_DISTANCE)
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let local = hit_point.minus(&self.center);
+        let side_distance = ((local.get_x().powi(2) + local.get_z().powi(2)).sqrt() - self.radius).abs();
+        if local.get_y().abs() < side_distance.min((local.get_y() - self.height).abs()) {
+            Vector::new(0.0, -1.0, 0.0)
+        } else if (local.get_y() - self.height).abs() < side_distance {
+            Vector::new(0.0, 1.0, 0.0)
+        } else {
+            Vector::new(local.get_x(), 0.0, local.get_z()).normalize()
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vector::new(self.radius, 0.0, self.radius);
+        BoundingBox::new(self.center.minus(&extent),
+                         self.center.plus(&extent).plus(&Vector::new(0.0, self.height, 0.0)))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        convex_interval(&self.crossings(ray))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(2.0 * PI * self.radius * (self.height + self.radius))
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        let cap = PI * self.radius * self.radius;
+        let (part, u) = pick_by_area(&[2.0 * PI * self.radius * self.height, cap, cap], u);
+        let phi = 2.0 * PI * v;
+        let (cos, sin) = (phi.cos(), phi.sin());
+        let (local, normal) = match part {
+            0 => (Vector::new(self.radius * cos, self.height * u, self.radius * sin), Vector::new(cos, 0.0, sin)),
+            // the caps are sampled like disks
+            _ => {
+                let r = self.radius * u.sqrt();
+                let (y, normal) = if part == 1 { (0.0, -1.0) } else { (self.height, 1.0) };
+                (Vector::new(r * cos, y, r * sin), Vector::new(0.0, normal, 0.0))
+            }
+        };
+        Some((self.center.plus(&local), normal))
+    }
+}
+
+impl Drawable for Cylinder {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for Cylinder {
+    /// Wraps the texture around the side once, starting at the bottom.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let hit_vec = hit_point.minus(&self.center);
+        let phi = hit_vec.get_z().atan2(hit_vec.get_x());
+        Point2D {
+            x: (1.0 + phi / PI) * 0.5,
+            y: (hit_vec.get_y() / self.height).clamp(0.0, 1.0),
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::assert_samples_on_surface;
+
+    fn cylinder() -> Cylinder {
+        Cylinder::new(Vector::zero(), 1.0, 2.0,
+                      Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_intersect() {
+        let cylinder = cylinder();
+        let side = Ray::from(Vector::new(0.0, 1.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cylinder.intersect(&side), Some(4.0));
+        assert_eq!(cylinder.surface_normal(&Vector::new(0.0, 1.0, 1.0)), Vector::new(0.0, 0.0, 1.0));
+
+        let top = Ray::from(Vector::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(cylinder.intersect(&top), Some(3.0));
+        assert_eq!(cylinder.surface_normal(&Vector::new(0.5, 2.0, 0.0)), Vector::new(0.0, 1.0, 0.0));
+
+        // passes above the top cap
+        let above = Ray::from(Vector::new(0.0, 2.5, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cylinder.intersect(&above), None);
+    }
+
+    #[test]
+    fn test_from_inside() {
+        let cylinder = cylinder();
+        let up = Ray::from(Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(cylinder.intersect(&up), Some(1.0));
+        let sideways = Ray::from(Vector::new(0.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(cylinder.intersect(&sideways), Some(1.0));
+    }
+
+    #[test]
+    fn test_sampled_surface() {
+        let cylinder = cylinder();
+        assert!((cylinder.surface_area().unwrap() - 6.0 * PI).abs() < 1e-12);
+        assert_samples_on_surface(&cylinder);
+    }
+
+    #[test]
+    fn test_grazing() {
+        let cylinder = cylinder();
+        // touching the side along a line
+        let tangent = Ray::from(Vector::new(1.0, 1.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cylinder.intersect(&tangent), Some(5.0));
+        let outside = Ray::from(Vector::new(1.0 + 1e-6, 1.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(cylinder.intersect(&outside), None);
+    }
+}
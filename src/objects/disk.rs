@@ -0,0 +1,126 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+use crate::base::{BoundingBox, Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use crate::material::Material;
+use crate::objects::MIN_DISTANCE;
+
+/// Flat circle, visible from both sides.
+pub struct Disk {
+    center: Vector,
+    normal: Vector,
+    radius: f64,
+    tangent: Vector,
+    bitangent: Vector,
+    material: Material,
+}
+
+impl Disk {
+    pub fn new(center: Vector, normal: Vector, radius: f64, material: Material) -> Disk {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Disk {
+            center: center,
+            normal: normal,
+            radius: radius,
+            tangent: tangent,
+            bitangent: bitangent,
+            material: material,
+        }
+    }
+    pub fn get_center(&self) -> &Vector {
+        &self.center
+    }
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Intersectable for Disk {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        let dot_product = self.normal.dot(ray.get_direction());
+        if dot_product == 0.0 {
+            return None;
+        }
+        let distance = self.center.minus(ray.get_origin()).dot(&self.normal) / dot_product;
+        if distance <= MIN_DISTANCE {
+            return None;
+        }
+        let hit_point = ray.get_origin().plus(&ray.get_direction().factor(distance));
+        if hit_point.minus(&self.center).euclidian_distance() > self.radius {
+            return None;
+        }
+        Some(distance)
+    }
+
+    fn surface_normal(&self, _: &Vector) -> Vector {
+        self.normal.clone()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        // extent of the circle along each axis
+        let extent = Vector::new(
+            self.radius * (1.0 - self.normal.get_x().powi(2)).max(0.0).sqrt(),
+            self.radius * (1.0 - self.normal.get_y().powi(2)).max(0.0).sqrt(),
+            self.radius * (1.0 - self.normal.get_z().powi(2)).max(0.0).sqrt(),
+        );
+        BoundingBox::new(self.center.minus(&extent), self.center.plus(&extent))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(PI * self.radius * self.radius)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        let r = self.radius * u.sqrt();
+        let phi = 2.0 * PI * v;
+        let point = self.center
+            .plus(&self.tangent.factor(r * phi.cos()))
+            .plus(&self.bitangent.factor(r * phi.sin()));
+        Some((point, self.normal.clone()))
+    }
+}
+
+impl Drawable for Disk {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for Disk {
+    /// Maps the disk into the unit square touching its edges.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let hit_vec = hit_point.minus(&self.center);
+        Point2D {
+            x: 0.5 + hit_vec.dot(&self.tangent) / (2.0 * self.radius),
+            y: 0.5 + hit_vec.dot(&self.bitangent) / (2.0 * self.radius),
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+
+    #[test]
+    fn test_intersect() {
+        let disk = Disk::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0), 1.0,
+                             Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0));
+        let down = Vector::new(0.0, -1.0, 0.0);
+        assert_eq!(disk.intersect(&Ray::from(Vector::new(0.5, 2.0, 0.5), down.clone())), Some(2.0));
+        assert_eq!(disk.intersect(&Ray::from(Vector::new(0.8, 2.0, 0.8), down)), None);
+        // visible from below as well
+        assert_eq!(disk.intersect(&Ray::from(Vector::new(0.0, -3.0, 0.0), Vector::new(0.0, 1.0, 0.0))), Some(3.0));
+        // a ray in the plane of the disk never hits it
+        assert_eq!(disk.intersect(&Ray::from(Vector::new(-2.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0))), None);
+
+        let bounds = disk.bounding_box();
+        assert_eq!(bounds.get_max(), &Vector::new(1.0, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::base::{BoundingBox, Color, Drawable, Intersectable, Intersection, Point2D, Ray, Textureable};
+use crate::bvh::Bvh;
+use crate::material::Material;
+use crate::objects::mesh::Mesh;
+use crate::vector::Vector;
+
+/// Objects with their own bounding volume hierarchy, placed in a scene as a single object. Hits
+/// report the member that was hit, which is shaded. Asked for its surface directly, a group
+/// answers with the member nearest to the point and with the material of its first member.
+pub struct Group {
+    objects: Vec<Box<dyn Drawable>>,
+    bvh: Bvh,
+    bounds: BoundingBox,
+}
+
+impl Group {
+    /// `objects` must not be empty.
+    pub fn new(objects: Vec<Box<dyn Drawable>>) -> Group {
+        assert!(!objects.is_empty(), "a group needs at least one object");
+        let bounds = objects.iter().fold(BoundingBox::empty(), |bounds, object| bounds.union(&object.bounding_box()));
+        Group {
+            bvh: Bvh::new(&objects),
+            objects: objects,
+            bounds: bounds,
+        }
+    }
+    /// The mesh must have at least one face.
+    pub fn from_mesh(mesh: Mesh) -> Group {
+        let triangles = mesh.into_triangles().into_iter()
+            .map(|triangle| Box::new(triangle) as Box<dyn Drawable>)
+            .collect();
+        Group::new(triangles)
+    }
+    pub fn get_objects(&self) -> &[Box<dyn Drawable>] {
+        &self.objects
+    }
+    /// Member whose bounds are nearest to `point`, the first of those that are equally near.
+    fn member_at(&self, point: &Vector) -> &dyn Drawable {
+        self.objects.iter()
+            .min_by(|a, b| a.bounding_box().distance(point).total_cmp(&b.bounding_box().distance(point)))
+            .unwrap()
+            .as_ref()
+    }
+}
+
+impl Intersectable for Group {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.hit(ray).map(|intersection| intersection.get_distance())
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        self.member_at(hit_point).surface_normal(hit_point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
+
+impl Textureable for Group {
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        self.member_at(hit_point).texture_coords(hit_point)
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        self.member_at(hit_point).get_texture_color(hit_point)
+    }
+}
+
+impl Drawable for Group {
+    fn get_material(&self) -> &Material {
+        self.objects[0].get_material()
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.bvh.trace(ray, &self.objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::mesh::Face;
+
+    #[test]
+    fn test_member_surface() {
+        // a triangle facing the z axis and one facing the x axis, away from it
+        let mesh = Mesh::new(
+            vec![Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0),
+                 Vector::new(3.0, 0.0, 0.0), Vector::new(3.0, 1.0, 0.0), Vector::new(3.0, 0.0, 1.0)],
+            Vec::new(), Vec::new(),
+            vec![Face { positions: [0, 1, 2], normals: None, uvs: None },
+                 Face { positions: [3, 4, 5], normals: None, uvs: None }],
+            Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0),
+        );
+        let group = Group::from_mesh(mesh);
+
+        assert_eq!(group.surface_normal(&Vector::new(0.25, 0.25, 0.0)), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(group.surface_normal(&Vector::new(3.0, 0.25, 0.25)), Vector::new(1.0, 0.0, 0.0));
+        assert!(!group.get_material().is_emissive());
+    }
+}
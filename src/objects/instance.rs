@@ -0,0 +1,290 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::base::{BoundingBox, Color, Drawable, Intersectable, Intersection, Point2D, Ray, Textureable};
+use crate::material::Material;
+use crate::transform::{Decomposition, Transform};
+use crate::vector::Vector;
+
+/// Times between two keyframes at which the bounds of a rotating instance are taken.
+const BOUND_STEPS: u32 = 32;
+
+/// Places a shared object in the scene with a transformation. Any number of instances can show the
+/// same object, be it a primitive or a whole `Group`, without copying it. An animated instance
+/// moves between transformations given at points in time.
+pub struct Instance {
+    object: Arc<dyn Drawable>,
+    /// Transformations sorted by their time, a single one for an instance that stays in place.
+    keyframes: Vec<(f64, Transform)>,
+    /// The keyframes taken apart, ready to be blended.
+    decompositions: Vec<Decomposition>,
+    bounds: BoundingBox,
+    /// Factor the area of the object grows by, `None` unless every keyframe scales it evenly and by
+    /// the same amount, which keeps points sampled on its surface spread uniformly.
+    area_scale: Option<f64>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Drawable>, transform: Transform) -> Instance {
+        Instance::animated(object, vec![(0.0, transform)])
+    }
+    /// Blends between the transformations of neighbouring `keyframes`, which have to be sorted by
+    /// time and must not be empty. Before the first and after the last one the instance stands still.
+    pub fn animated(object: Arc<dyn Drawable>, keyframes: Vec<(f64, Transform)>) -> Instance {
+        let decompositions: Vec<Decomposition> = keyframes.iter().map(|(_, transform)| transform.decompose()).collect();
+        let object_bounds = object.bounding_box();
+        let mut bounds = keyframes.iter()
+            .map(|(_, transform)| transform.apply_bounds(&object_bounds))
+            .fold(BoundingBox::empty(), |bounds, keyframe| bounds.union(&keyframe));
+        // without a rotation every corner of the box moves in a straight line between the keyframes,
+        // a rotating one is followed along its arc
+        for pair in decompositions.windows(2) {
+            let turn = pair[0].turn(&pair[1]);
+            if turn == 0.0 {
+                continue;
+            }
+            for step in 0..=BOUND_STEPS {
+                if let Some(transform) = pair[0].interpolate(&pair[1], step as f64 / BOUND_STEPS as f64) {
+                    bounds = bounds.union(&swept_bounds(&transform, &object_bounds, turn / BOUND_STEPS as f64));
+                }
+            }
+        }
+        let scale = keyframes[0].1.uniform_scale();
+        let area_scale = scale
+            .filter(|&scale| keyframes.iter().all(|(_, transform)| {
+                transform.uniform_scale().is_some_and(|other| (other - scale).abs() < 1e-9 * scale)
+            }))
+            .map(|scale| scale * scale);
+        Instance {
+            object: object,
+            keyframes: keyframes,
+            decompositions: decompositions,
+            bounds: bounds,
+            area_scale: area_scale,
+        }
+    }
+    /// Transformation at time 0, or at the first keyframe of an animation.
+    pub fn get_transform(&self) -> &Transform {
+        &self.keyframes[0].1
+    }
+    /// Transformation at `time`, borrowed unless it is blended between two keyframes.
+    pub fn transform_at(&self, time: f64) -> Cow<'_, Transform> {
+        let next = self.keyframes.partition_point(|&(keyframe_time, _)| keyframe_time <= time);
+        if next == 0 {
+            return Cow::Borrowed(&self.keyframes[0].1);
+        }
+        if next == self.keyframes.len() {
+            return Cow::Borrowed(&self.keyframes[next - 1].1);
+        }
+        let (start_time, start) = &self.keyframes[next - 1];
+        let (end_time, end) = &self.keyframes[next];
+        let t = (time - start_time) / (end_time - start_time);
+        match self.decompositions[next - 1].interpolate(&self.decompositions[next], t) {
+            Some(transform) => Cow::Owned(transform),
+            // a blend from a mirrored to an unmirrored keyframe collapses, the nearer one has to do
+            None => Cow::Borrowed(if t < 0.5 { start } else { end }),
+        }
+    }
+}
+
+/// Bounds of the object at one step of a rotating animation, grown by how far the corners can
+/// bulge out from the straight line while turning by `step_turn` to the next step.
+fn swept_bounds(transform: &Transform, object_bounds: &BoundingBox, step_turn: f64) -> BoundingBox {
+    let bounds = transform.apply_bounds(object_bounds);
+    if !bounds.is_finite() {
+        return bounds;
+    }
+    let pivot = transform.apply_point(&Vector::zero());
+    let radius = bounds.centroid().minus(&pivot).euclidian_distance() + bounds.extent().euclidian_distance() / 2.0;
+    let bulge = radius * (1.0 - (step_turn / 2.0).cos());
+    let margin = Vector::new(bulge, bulge, bulge);
+    BoundingBox::new(bounds.get_min().minus(&margin), bounds.get_max().plus(&margin))
+}
+
+impl Intersectable for Instance {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        let (local_ray, scale) = self.transform_at(ray.get_time()).invert_ray(ray);
+        self.object.intersect(&local_ray).map(|distance| distance / scale)
+    }
+
+    /// Normal of the instance at time 0, `Intersection::surface_normal` knows the time of the hit.
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let transform = self.get_transform();
+        let normal = self.object.surface_normal(&transform.invert_point(hit_point));
+        transform.apply_normal(&normal).normalize()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let (local_ray, scale) = self.transform_at(ray.get_time()).invert_ray(ray);
+        self.object.intervals(&local_ray).into_iter()
+            .map(|(entry, exit)| (entry / scale, exit / scale))
+            .collect()
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(self.object.surface_area()? * self.area_scale?)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, time: f64) -> Option<(Vector, Vector)> {
+        self.area_scale?;
+        let (point, normal) = self.object.sample_surface(u, v, time)?;
+        let transform = self.transform_at(time);
+        Some((transform.apply_point(&point), transform.apply_normal(&normal).normalize()))
+    }
+}
+
+impl Textureable for Instance {
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        self.object.texture_coords(&self.get_transform().invert_point(hit_point))
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        self.object.get_texture_color(&self.get_transform().invert_point(hit_point))
+    }
+}
+
+impl Drawable for Instance {
+    fn get_material(&self) -> &Material {
+        self.object.get_material()
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let transform = self.transform_at(ray.get_time());
+        let (local_ray, scale) = transform.invert_ray(ray);
+        let intersection = self.object.hit(&local_ray)?;
+        let distance = intersection.get_distance() / scale;
+        Some(intersection.transformed(distance, &transform))
+    }
+
+    fn hit_at(&self, ray: &Ray, distance: f64) -> Intersection<'_> {
+        let transform = self.transform_at(ray.get_time());
+        let (local_ray, scale) = transform.invert_ray(ray);
+        self.object.hit_at(&local_ray, distance * scale).transformed(distance, &transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::group::Group;
+    use crate::objects::mesh::{Face, Mesh};
+    use crate::objects::sphere::Sphere;
+
+    fn material() -> Material {
+        Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0)
+    }
+
+    #[test]
+    fn test_ellipsoid() {
+        let sphere: Arc<dyn Drawable> = Arc::new(Sphere::new(Vector::zero(), 1.0, material()));
+        let transform = Transform::identity()
+            .scale(&Vector::new(1.0, 3.0, 1.0)).unwrap()
+            .translate(&Vector::new(0.0, 0.0, -10.0));
+        let ellipsoid = Instance::new(sphere, transform);
+
+        let along_z = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        assert!((ellipsoid.intersect(&along_z).unwrap() - 9.0).abs() < 1e-9);
+        // the point (0, 2.5, -10) is inside the stretched sphere only
+        let above = Ray::from(Vector::new(0.0, 2.5, 0.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(ellipsoid.intersect(&above).is_some());
+
+        let intersection = ellipsoid.hit(&along_z).unwrap();
+        assert_eq!(intersection.surface_normal(&Vector::new(0.0, 0.0, -9.0)), Vector::new(0.0, 0.0, 1.0));
+        let bounds = ellipsoid.bounding_box();
+        assert_eq!(bounds.get_max(), &Vector::new(1.0, 3.0, -9.0));
+    }
+
+    #[test]
+    fn test_rotated_mesh() {
+        let mesh = Mesh::new(
+            vec![Vector::new(-1.0, -1.0, 0.0), Vector::new(1.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)],
+            Vec::new(), Vec::new(),
+            vec![Face { positions: [0, 1, 2], normals: None, uvs: None }],
+            material(),
+        );
+        let group: Arc<dyn Drawable> = Arc::new(Group::from_mesh(mesh));
+        // turned to face the x axis and moved away from the origin
+        let transform = Transform::identity().rotate_y(90.0).translate(&Vector::new(5.0, 0.0, 0.0));
+        let instance = Instance::new(group, transform);
+
+        let ray = Ray::from(Vector::zero(), Vector::new(1.0, 0.0, 0.0));
+        let intersection = instance.hit(&ray).unwrap();
+        assert!((intersection.get_distance() - 5.0).abs() < 1e-9);
+        let normal = intersection.surface_normal(&Vector::new(5.0, 0.0, 0.0));
+        assert!((normal.get_x().abs() - 1.0).abs() < 1e-9);
+        assert!(!intersection.get_object().get_material().is_emissive());
+        // the instanced group answers for its surface too, with the triangle found there
+        let normal = instance.surface_normal(&Vector::new(5.0, 0.0, 0.0));
+        assert!((normal.get_x().abs() - 1.0).abs() < 1e-9);
+        assert!(!instance.get_material().is_emissive());
+        assert!(instance.hit(&Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0))).is_none());
+    }
+
+    #[test]
+    fn test_sampled_surface() {
+        let sphere: Arc<dyn Drawable> = Arc::new(Sphere::new(Vector::zero(), 1.0, material()));
+        let transform = Transform::identity()
+            .scale(&Vector::new(2.0, 2.0, 2.0)).unwrap()
+            .translate(&Vector::new(0.0, 0.0, -10.0));
+        let sphere = Instance::new(sphere, transform);
+        assert!((sphere.surface_area().unwrap() - 16.0 * PI).abs() < 1e-9);
+        let (point, normal) = sphere.sample_surface(0.3, 0.6, 0.0).unwrap();
+        assert!((point.minus(&Vector::new(0.0, 0.0, -10.0)).euclidian_distance() - 2.0).abs() < 1e-9);
+        assert!((normal.euclidian_distance() - 1.0).abs() < 1e-9);
+
+        // points on a stretched surface would not be spread evenly
+        let ellipsoid = Instance::new(Arc::new(Sphere::new(Vector::zero(), 1.0, material())),
+                                      Transform::identity().scale(&Vector::new(1.0, 3.0, 1.0)).unwrap());
+        assert_eq!(ellipsoid.surface_area(), None);
+        assert!(ellipsoid.sample_surface(0.3, 0.6, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let sphere: Arc<dyn Drawable> = Arc::new(Sphere::new(Vector::zero(), 1.0, material()));
+        let keyframes = vec![
+            (0.0, Transform::identity().translate(&Vector::new(0.0, 0.0, -5.0))),
+            (1.0, Transform::identity().translate(&Vector::new(4.0, 0.0, -5.0))),
+            (2.0, Transform::identity().translate(&Vector::new(4.0, 4.0, -5.0))),
+        ];
+        let instance = Instance::animated(sphere, keyframes);
+        assert_eq!(instance.bounding_box().get_max(), &Vector::new(5.0, 5.0, -4.0));
+
+        let ray = Ray::from(Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(instance.hit(&ray).is_none());
+        let intersection = instance.hit(&ray.clone().with_time(0.5)).unwrap();
+        assert!((intersection.get_distance() - 4.0).abs() < 1e-9);
+        assert_eq!(intersection.surface_normal(&Vector::new(2.0, 0.0, -4.0)), Vector::new(0.0, 0.0, 1.0));
+        // it stays at the last keyframe
+        let above = Ray::from(Vector::new(4.0, 4.0, 0.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(instance.hit(&above.with_time(7.0)).is_some());
+    }
+
+    #[test]
+    fn test_rotation_stays_rigid() {
+        // a unit sphere on an arm of length 4, swung around the y axis
+        let sphere: Arc<dyn Drawable> = Arc::new(Sphere::new(Vector::new(4.0, 0.0, 0.0), 1.0, material()));
+        let keyframes = vec![
+            (0.0, Transform::identity()),
+            (1.0, Transform::identity().rotate_y(160.0)),
+        ];
+        let instance = Instance::animated(sphere, keyframes);
+
+        // halfway the sphere is still on the arm and keeps its radius of 1
+        let center = Transform::identity().rotate_y(80.0).apply_point(&Vector::new(4.0, 0.0, 0.0));
+        let down = |x: f64| Ray::from(Vector::new(x, 10.0, center.get_z()), Vector::new(0.0, -1.0, 0.0)).with_time(0.5);
+        let intersection = instance.hit(&down(center.get_x())).unwrap();
+        assert!((intersection.get_distance() - 9.0).abs() < 1e-9);
+        assert!(instance.hit(&down(center.get_x() + 0.99)).is_some());
+        assert!(instance.hit(&down(center.get_x() + 1.01)).is_none());
+        // the bounds reach out to the arc, beyond the boxes of the keyframes
+        assert!(instance.bounding_box().get_min().get_z() <= -5.0);
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::base::Point2D;
+use crate::material::Material;
+use crate::objects::triangle::Triangle;
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+/// Indices of a single triangle into the vertex attribute lists of a `Mesh`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+pub struct Mesh {
+    positions: Vec<Vector>,
+    normals: Vec<Vector>,
+    uvs: Vec<Point2D>,
+    faces: Vec<Face>,
+    material: Material,
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Vector>, normals: Vec<Vector>, uvs: Vec<Point2D>,
+               faces: Vec<Face>, material: Material) -> Mesh {
+        Mesh {
+            positions: positions,
+            normals: normals,
+            uvs: uvs,
+            faces: faces,
+            material: material,
+        }
+    }
+    /// Moves the vertices into place, for meshes that cannot be shown through an `Instance`.
+    pub fn transformed(mut self, transform: &Transform) -> Mesh {
+        for position in self.positions.iter_mut() {
+            *position = transform.apply_point(position);
+        }
+        for normal in self.normals.iter_mut() {
+            *normal = transform.apply_normal(normal).normalize();
+        }
+        self
+    }
+    /// Splits the mesh into triangles which share the vertex data and the material.
+    pub fn into_triangles(self) -> Vec<Triangle> {
+        let face_count = self.faces.len();
+        let mesh = Arc::new(self);
+        (0..face_count).map(|face| Triangle::new(mesh.clone(), face)).collect()
+    }
+    pub fn get_position(&self, index: usize) -> &Vector {
+        &self.positions[index]
+    }
+    pub fn get_normal(&self, index: usize) -> &Vector {
+        &self.normals[index]
+    }
+    pub fn get_uv(&self, index: usize) -> &Point2D {
+        &self.uvs[index]
+    }
+    pub fn get_faces(&self) -> &[Face] {
+        &self.faces
+    }
+    pub fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
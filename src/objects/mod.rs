@@ -1,2 +1,73 @@
 pub mod sphere;
 pub mod plane;
+pub mod triangle;
+pub mod mesh;
+pub mod group;
+pub mod instance;
+pub(crate) mod polynomial;
+pub mod cuboid;
+pub mod cylinder;
+pub mod cone;
+pub mod disk;
+pub mod torus;
+pub mod csg;
+pub mod sdf;
+
+pub use crate::objects::polynomial::Roots;
+
+use crate::base::{Drawable, Intersection};
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+/// Hits closer than this are ignored by the analytic primitives, so rays leaving a surface do not
+/// hit it again.
+pub(crate) const MIN_DISTANCE: f64 = 1e-9;
+
+/// Span of a ray inside a convex solid, from the first to the last of its sorted crossings.
+pub(crate) fn convex_interval(crossings: &[f64]) -> Vec<(f64, f64)> {
+    match (crossings.first(), crossings.last()) {
+        (Some(&entry), Some(&exit)) => vec![(entry, exit)],
+        _ => Vec::new(),
+    }
+}
+
+/// Picks one of the parts of a surface with `areas` for `u` in `[0, 1)`, each as often as its
+/// share of the total area, and stretches `u` back over the share of the part.
+pub(crate) fn pick_by_area(areas: &[f64], u: f64) -> (usize, f64) {
+    let mut target = u * areas.iter().sum::<f64>();
+    for (index, &area) in areas.iter().enumerate() {
+        if target < area {
+            return (index, target / area);
+        }
+        target -= area;
+    }
+    // rounding can carry a `u` close to 1 past the end
+    (areas.iter().rposition(|&area| area > 0.0).unwrap_or(0), 1.0)
+}
+
+/// Position along a straight movement at `time`, starting at `start` at time 0 and ending at `end`
+/// at time 1. Outside of that the object stays at the nearer end.
+pub(crate) fn moved(start: &Vector, end: &Vector, time: f64) -> Vector {
+    start.plus(&end.minus(start).factor(time.clamp(0.0, 1.0)))
+}
+
+/// Intersection with an object that has moved by `offset` since time 0. It is shaded where it was
+/// at time 0, so normals and textures move along with it.
+pub(crate) fn moved_hit<'a>(object: &'a dyn Drawable, distance: f64, offset: &Vector) -> Intersection<'a> {
+    Intersection::new(distance, object).transformed(distance, &Transform::identity().translate(offset))
+}
+
+/// Checks that points sampled over a grid lie on the surface of `object` with its normal there.
+#[cfg(test)]
+pub(crate) fn assert_samples_on_surface(object: &dyn Drawable) {
+    let steps = 8;
+    for i in 0..steps {
+        for j in 0..steps {
+            let (u, v) = ((i as f64 + 0.5) / steps as f64, (j as f64 + 0.5) / steps as f64);
+            let (point, normal) = object.sample_surface(u, v, 0.0).unwrap();
+            assert!(object.bounding_box().distance(&point) < 1e-9, "{:?} is outside", point);
+            let expected = object.surface_normal(&point);
+            assert!(normal.minus(&expected).euclidian_distance() < 1e-9, "{:?} != {:?} at {:?}", normal, expected, point);
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use crate::vector::Vector;
-use crate::base::{Drawable, Intersectable, Ray, Color, Point2D, Textureable};
+use crate::base::{BoundingBox, Drawable, Intersectable, Intersection, Ray, Color, Point2D, Textureable};
 use crate::material::Material;
+use crate::objects::{moved, moved_hit};
 
 pub struct Plane {
     normal: Vector,
     point: Vector,
-    material: Material
+    material: Material,
+    /// Point the plane passes through at time 1 for a moving plane, at time 0 it is `point`.
+    end_point: Option<Vector>,
 }
 
 impl Plane {
@@ -14,6 +17,17 @@ impl Plane {
             normal: normal,
             point: point,
             material: material,
+            end_point: None,
+        }
+    }
+    /// Moves the plane in a straight line, passing through `end_point` at time 1.
+    pub fn set_end_point(&mut self, end_point: Vector) {
+        self.end_point = Some(end_point);
+    }
+    pub fn point_at(&self, time: f64) -> Vector {
+        match &self.end_point {
+            Some(end_point) => moved(&self.point, end_point, time),
+            None => self.point.clone(),
         }
     }
 }
@@ -24,7 +38,7 @@ impl Intersectable for Plane {
         if dot_product > 1e-4 {
             return None;
         }
-        let v = self.point.minus(ray.get_origin());
+        let v = self.point_at(ray.get_time()).minus(ray.get_origin());
         let distance = v.dot(&self.normal) / dot_product;
         if distance >= 0.0 {
             return Some(distance);
@@ -35,12 +49,42 @@ impl Intersectable for Plane {
     fn surface_normal(&self, _: &Vector) -> Vector {
         self.normal.clone()
     }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+
+    /// The solid side of a plane is the half-space behind it.
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let height = ray.get_origin().minus(&self.point_at(ray.get_time())).dot(&self.normal);
+        let dot_product = self.normal.dot(ray.get_direction());
+        if dot_product == 0.0 {
+            return if height <= 0.0 { vec![(f64::NEG_INFINITY, f64::INFINITY)] } else { Vec::new() };
+        }
+        let distance = -height / dot_product;
+        if dot_product > 0.0 {
+            vec![(f64::NEG_INFINITY, distance)]
+        } else {
+            vec![(distance, f64::INFINITY)]
+        }
+    }
 }
 
 impl Drawable for Plane {
     fn get_material(&self) -> &Material {
         &self.material
     }
+
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.intersect(ray).map(|distance| self.hit_at(ray, distance))
+    }
+
+    fn hit_at(&self, ray: &Ray, distance: f64) -> Intersection<'_> {
+        match &self.end_point {
+            Some(_) => moved_hit(self, distance, &self.point_at(ray.get_time()).minus(&self.point)),
+            None => Intersection::new(distance, self),
+        }
+    }
 }
 
 impl Textureable for Plane {
@@ -65,6 +109,6 @@ impl Textureable for Plane {
 
     fn get_texture_color(&self, hit_point: &Vector) -> Color {
         let tex_coords = self.texture_coords(hit_point);
-        self.material.get_texture().get_color(tex_coords.x, tex_coords.y)
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
     }
 }
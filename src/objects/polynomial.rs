@@ -0,0 +1,222 @@
+//! Real roots of low degree polynomials, as needed for ray intersections with quadric and quartic
+//! surfaces.
+
+use std::f64::consts::PI;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+
+/// Discriminants this close to zero are treated as a double root, so grazing rays still hit.
+const TOLERANCE: f64 = 1e-12;
+
+/// Up to four numbers kept on the stack, the real roots of a polynomial or the distances at which a
+/// ray crosses a surface. Intersection tests run for every ray, so they do not allocate.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    pub fn new() -> Roots {
+        Roots::default()
+    }
+    pub fn from_slice(values: &[f64]) -> Roots {
+        values.iter().copied().collect()
+    }
+    /// Adds a number, there is room for four.
+    pub fn push(&mut self, value: f64) {
+        self.values[self.len] = value;
+        self.len += 1;
+    }
+    /// Removes consecutive repeated numbers.
+    pub fn dedup(&mut self) {
+        let mut len = 0;
+        for i in 0..self.len {
+            if len == 0 || self.values[i] != self.values[len - 1] {
+                self.values[len] = self.values[i];
+                len += 1;
+            }
+        }
+        self.len = len;
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+impl DerefMut for Roots {
+    fn deref_mut(&mut self) -> &mut [f64] {
+        &mut self.values[..self.len]
+    }
+}
+
+impl FromIterator<f64> for Roots {
+    fn from_iter<I: IntoIterator<Item=f64>>(iter: I) -> Roots {
+        let mut roots = Roots::new();
+        for value in iter {
+            roots.push(value);
+        }
+        roots
+    }
+}
+
+impl IntoIterator for Roots {
+    type Item = f64;
+    type IntoIter = std::iter::Take<std::array::IntoIter<f64, 4>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self.values).take(self.len)
+    }
+}
+
+/// Real roots of `a x² + b x + c` in ascending order.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    if a == 0.0 {
+        if b == 0.0 {
+            return Roots::new();
+        }
+        return Roots::from_slice(&[-c / b]);
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < -TOLERANCE * (b * b).max(1.0) {
+        return Roots::new();
+    }
+    let root = discriminant.max(0.0).sqrt();
+    // avoids cancellation between -b and the root
+    let q = -0.5 * (b + b.signum() * root);
+    if q == 0.0 {
+        return Roots::from_slice(&[0.0, 0.0]);
+    }
+    let (x0, x1) = (q / a, c / q);
+    if x0 < x1 { Roots::from_slice(&[x0, x1]) } else { Roots::from_slice(&[x1, x0]) }
+}
+
+/// Real roots of the monic cubic `x³ + a x² + b x + c`, in no particular order.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Roots {
+    let shift = a / 3.0;
+    // depressed cubic t³ + p t + q with x = t - a / 3
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        Roots::from_slice(&[(-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() - shift])
+    } else if p == 0.0 {
+        Roots::from_slice(&[-shift])
+    } else {
+        // three real roots, found with the trigonometric method
+        let r = (-p / 3.0).sqrt();
+        let phi = (3.0 * q / (2.0 * p * r)).clamp(-1.0, 1.0).acos() / 3.0;
+        (0..3).map(|k| 2.0 * r * (phi - 2.0 * PI * k as f64 / 3.0).cos() - shift).collect()
+    }
+}
+
+/// Real roots of `a x⁴ + b x³ + c x² + d x + e` in ascending order, found with Ferrari's method and
+/// refined with Newton's method.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    if a == 0.0 {
+        return Roots::new();
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let shift = b / 4.0;
+    // depressed quartic y⁴ + p y² + q y + r with x = y - b / 4
+    let p = c - 3.0 * b * b / 8.0;
+    let q = d - b * c / 2.0 + b * b * b / 8.0;
+    let r = e - b * d / 4.0 + b * b * c / 16.0 - 3.0 * b * b * b * b / 256.0;
+
+    let mut roots = Roots::new();
+    if q.abs() < TOLERANCE {
+        // biquadratic
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= -TOLERANCE {
+                let y = z.max(0.0).sqrt();
+                roots.push(y - shift);
+                roots.push(-y - shift);
+            }
+        }
+    } else {
+        let m = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0).into_iter().fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return Roots::new();
+        }
+        let s = (2.0 * m).sqrt();
+        for &(sign, offset) in [(1.0, -q / (2.0 * s)), (-1.0, q / (2.0 * s))].iter() {
+            for y in solve_quadratic(1.0, sign * s, p / 2.0 + m + offset) {
+                roots.push(y - shift);
+            }
+        }
+    }
+
+    let polynomial = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let derivative = |x: f64| ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+    for root in roots.iter_mut() {
+        for _ in 0..3 {
+            let slope = derivative(*root);
+            if slope == 0.0 {
+                break;
+            }
+            // steps next to double roots can shoot far off, only keep improvements
+            let refined = *root - polynomial(*root) / slope;
+            if polynomial(refined).abs() >= polynomial(*root).abs() {
+                break;
+            }
+            *root = refined;
+        }
+    }
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal));
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut roots: Roots = [3.0, 1.0, 1.0, 2.0].iter().copied().collect();
+        roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        roots.dedup();
+        assert_eq!(&roots[..], &[1.0, 2.0, 3.0]);
+        assert_eq!(roots.into_iter().sum::<f64>(), 6.0);
+    }
+
+    #[test]
+    fn test_quadratic() {
+        assert_roots(&solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(&solve_quadratic(1.0, -2.0, 1.0), &[1.0, 1.0]);
+        assert_roots(&solve_quadratic(1.0, 0.0, 1.0), &[]);
+        assert_roots(&solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // (x - 1)(x - 2)(x + 3)
+        let mut roots = solve_cubic(0.0, -7.0, 6.0);
+        roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert_roots(&roots, &[-3.0, 1.0, 2.0]);
+        assert_roots(&solve_cubic(0.0, 0.0, -8.0), &[2.0]);
+    }
+
+    #[test]
+    fn test_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(&solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), &[1.0, 2.0, 3.0, 4.0]);
+        // (x² + 1)(x - 1)(x + 2)
+        assert_roots(&solve_quartic(2.0, 2.0, -2.0, 2.0, -4.0), &[-2.0, 1.0]);
+        // x⁴ - 5x² + 4 is biquadratic
+        assert_roots(&solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0]);
+        assert_roots(&solve_quartic(1.0, 0.0, 1.0, 0.0, 1.0), &[]);
+    }
+}
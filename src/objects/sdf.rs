@@ -0,0 +1,348 @@
+//! Objects given by a signed distance function, intersected by sphere tracing.
+
+use crate::base::{BoundingBox, Color, Drawable, Intersectable, Point2D, Ray, Textureable};
+use crate::material::Material;
+use crate::vector::Vector;
+
+/// Signed distance to a surface, negative inside of it. The distance may be underestimated, which
+/// only costs more steps, but overestimating it lets rays skip through the surface.
+pub trait DistanceField: Send + Sync {
+    fn distance(&self, point: &Vector) -> f64;
+}
+
+impl<F: Fn(&Vector) -> f64 + Send + Sync> DistanceField for F {
+    fn distance(&self, point: &Vector) -> f64 {
+        self(point)
+    }
+}
+
+/// Distance field composed from shapes centred at the origin and operations on them.
+pub enum SdfNode {
+    Sphere { radius: f64 },
+    /// Box with the given distances from the centre to its faces.
+    Box { half_size: Vector },
+    /// Torus in the xz plane.
+    Torus { major_radius: f64, minor_radius: f64 },
+    /// The Mandelbulb fractal, about 1.2 units in radius for the usual power of 8.
+    Mandelbulb { power: f64, iterations: u32 },
+    Translate { offset: Vector, node: Box<SdfNode> },
+    Union(Box<SdfNode>, Box<SdfNode>),
+    /// Union blending the shapes into each other where they are closer than `smoothness`.
+    SmoothUnion { left: Box<SdfNode>, right: Box<SdfNode>, smoothness: f64 },
+    /// Repeats the cell around the origin with the given period on each axis, 0 keeps an axis
+    /// as it is. The shape has to fit into the cell.
+    Repeat { period: Vector, node: Box<SdfNode> },
+    /// Rotates the shape around the y axis by `rate` degrees per unit of height.
+    Twist { rate: f64, node: Box<SdfNode> },
+    /// Offsets the surface by a sine pattern with the given `amplitude` and `frequency`.
+    Displace { amplitude: f64, frequency: f64, node: Box<SdfNode> },
+}
+
+impl SdfNode {
+    /// Box around the shape, infinite along repeated axes.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let cube = |size: f64| BoundingBox::new(Vector::new(-size, -size, -size), Vector::new(size, size, size));
+        match self {
+            SdfNode::Sphere { radius } => cube(*radius),
+            SdfNode::Box { half_size } => BoundingBox::new(half_size.neg(), half_size.clone()),
+            SdfNode::Torus { major_radius, minor_radius } => {
+                let extent = Vector::new(major_radius + minor_radius, *minor_radius, major_radius + minor_radius);
+                BoundingBox::new(extent.neg(), extent)
+            }
+            // all points of the set stay within the escape radius
+            SdfNode::Mandelbulb { .. } => cube(2.0),
+            SdfNode::Translate { offset, node } => {
+                let bounds = node.bounding_box();
+                BoundingBox::new(bounds.get_min().plus(offset), bounds.get_max().plus(offset))
+            }
+            SdfNode::Union(left, right) => left.bounding_box().union(&right.bounding_box()),
+            SdfNode::SmoothUnion { left, right, smoothness } => {
+                let bounds = left.bounding_box().union(&right.bounding_box());
+                let margin = Vector::new(*smoothness, *smoothness, *smoothness);
+                BoundingBox::new(bounds.get_min().minus(&margin), bounds.get_max().plus(&margin))
+            }
+            SdfNode::Repeat { period, node } => {
+                let bounds = node.bounding_box();
+                let pick = |axis: usize, bound: &Vector, infinity: f64| if period.get_axis(axis) > 0.0 {
+                    infinity
+                } else {
+                    bound.get_axis(axis)
+                };
+                let (min, max) = (bounds.get_min(), bounds.get_max());
+                BoundingBox::new(Vector::new(pick(0, min, f64::NEG_INFINITY), pick(1, min, f64::NEG_INFINITY),
+                                             pick(2, min, f64::NEG_INFINITY)),
+                                 Vector::new(pick(0, max, f64::INFINITY), pick(1, max, f64::INFINITY),
+                                             pick(2, max, f64::INFINITY)))
+            }
+            SdfNode::Twist { node, .. } => {
+                // any rotation around the y axis stays within the circle around the box
+                let bounds = node.bounding_box();
+                let (min, max) = (bounds.get_min(), bounds.get_max());
+                let radius = min.get_x().abs().max(max.get_x().abs())
+                    .hypot(min.get_z().abs().max(max.get_z().abs()));
+                BoundingBox::new(Vector::new(-radius, min.get_y(), -radius), Vector::new(radius, max.get_y(), radius))
+            }
+            SdfNode::Displace { amplitude, node, .. } => {
+                let bounds = node.bounding_box();
+                let margin = Vector::new(amplitude.abs(), amplitude.abs(), amplitude.abs());
+                BoundingBox::new(bounds.get_min().minus(&margin), bounds.get_max().plus(&margin))
+            }
+        }
+    }
+}
+
+impl DistanceField for SdfNode {
+    fn distance(&self, point: &Vector) -> f64 {
+        match self {
+            SdfNode::Sphere { radius } => point.euclidian_distance() - radius,
+            SdfNode::Box { half_size } => {
+                let q = Vector::new(point.get_x().abs(), point.get_y().abs(), point.get_z().abs()).minus(half_size);
+                let inside = q.get_x().max(q.get_y()).max(q.get_z()).min(0.0);
+                q.max(&Vector::zero()).euclidian_distance() + inside
+            }
+            SdfNode::Torus { major_radius, minor_radius } => {
+                let ring = (point.get_x().powi(2) + point.get_z().powi(2)).sqrt() - major_radius;
+                (ring * ring + point.get_y().powi(2)).sqrt() - minor_radius
+            }
+            SdfNode::Mandelbulb { power, iterations } => mandelbulb(point, *power, *iterations),
+            SdfNode::Translate { offset, node } => node.distance(&point.minus(offset)),
+            SdfNode::Union(left, right) => left.distance(point).min(right.distance(point)),
+            SdfNode::SmoothUnion { left, right, smoothness } => {
+                let (a, b) = (left.distance(point), right.distance(point));
+                let h = (0.5 + 0.5 * (b - a) / smoothness).clamp(0.0, 1.0);
+                b + (a - b) * h - smoothness * h * (1.0 - h)
+            }
+            SdfNode::Repeat { period, node } => {
+                let wrap = |value: f64, period: f64| if period > 0.0 {
+                    value - period * (value / period).round()
+                } else {
+                    value
+                };
+                node.distance(&Vector::new(wrap(point.get_x(), period.get_x()), wrap(point.get_y(), period.get_y()),
+                                           wrap(point.get_z(), period.get_z())))
+            }
+            SdfNode::Twist { rate, node } => {
+                let (sin, cos) = (-rate.to_radians() * point.get_y()).sin_cos();
+                node.distance(&Vector::new(cos * point.get_x() - sin * point.get_z(), point.get_y(),
+                                           sin * point.get_x() + cos * point.get_z()))
+            }
+            SdfNode::Displace { amplitude, frequency, node } => {
+                let pattern = (frequency * point.get_x()).sin() * (frequency * point.get_y()).sin()
+                    * (frequency * point.get_z()).sin();
+                node.distance(point) + amplitude * pattern
+            }
+        }
+    }
+}
+
+/// Distance estimate of the Mandelbulb from the running derivative of its iteration.
+fn mandelbulb(point: &Vector, power: f64, iterations: u32) -> f64 {
+    let mut z = point.clone();
+    let mut derivative = 1.0;
+    let mut radius = z.euclidian_distance();
+    for _ in 0..iterations {
+        if radius > 2.0 {
+            break;
+        }
+        derivative = power * radius.powf(power - 1.0) * derivative + 1.0;
+        let theta = (z.get_y() / radius).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.get_z().atan2(z.get_x()) * power;
+        z = Vector::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+            .factor(radius.powf(power))
+            .plus(point);
+        radius = z.euclidian_distance();
+    }
+    if radius == 0.0 {
+        return 0.0;
+    }
+    0.5 * radius.ln() * radius / derivative
+}
+
+/// Surface of a distance field within `bounds`, found by stepping along rays by the distance to
+/// the surface until it is closer than `epsilon`.
+pub struct SdfObject {
+    field: Box<dyn DistanceField>,
+    bounds: BoundingBox,
+    material: Material,
+    epsilon: f64,
+    max_steps: u32,
+    step_scale: f64,
+    max_distance: f64,
+}
+
+impl SdfObject {
+    pub fn new(field: Box<dyn DistanceField>, bounds: BoundingBox, material: Material) -> SdfObject {
+        SdfObject {
+            field: field,
+            bounds: bounds,
+            material: material,
+            epsilon: 1e-4,
+            max_steps: 256,
+            step_scale: 1.0,
+            max_distance: 1000.0,
+        }
+    }
+    /// Distance to the surface at which a ray counts as hitting it, `1e-4` by default.
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = epsilon;
+    }
+    /// Steps after which a ray counts as missing, 256 by default.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+    /// Fraction of the distance to step at a time, 1 by default. Fields that overestimate the
+    /// distance, like strong twists and displacements, need smaller steps.
+    pub fn set_step_scale(&mut self, step_scale: f64) {
+        self.step_scale = step_scale;
+    }
+    /// Limit on the length of rays, for fields with infinite bounds. 1000 by default.
+    pub fn set_max_distance(&mut self, max_distance: f64) {
+        self.max_distance = max_distance;
+    }
+    pub fn get_epsilon(&self) -> f64 {
+        self.epsilon
+    }
+    pub fn get_max_steps(&self) -> u32 {
+        self.max_steps
+    }
+    pub fn distance(&self, point: &Vector) -> f64 {
+        self.field.distance(point)
+    }
+}
+
+impl Intersectable for SdfObject {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        let direction = ray.get_direction();
+        let inverse_direction = Vector::new(1.0 / direction.get_x(), 1.0 / direction.get_y(), 1.0 / direction.get_z());
+        let (mut distance, far) = self.bounds.span(ray.get_origin(), &inverse_direction, self.max_distance)?;
+        // Rays leaving the surface start within epsilon of it and only count hits after getting away.
+        let mut left_surface = distance > 0.0;
+        for _ in 0..self.max_steps {
+            let point = ray.get_origin().plus(&direction.factor(distance));
+            let step = self.field.distance(&point).abs();
+            if step < self.epsilon {
+                if left_surface {
+                    return Some(distance);
+                }
+            } else {
+                left_surface = true;
+            }
+            distance += (step * self.step_scale).max(self.epsilon);
+            if distance > far {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Gradient of the field by central differences.
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let h = self.epsilon;
+        let difference = |offset: Vector| {
+            self.field.distance(&hit_point.plus(&offset)) - self.field.distance(&hit_point.minus(&offset))
+        };
+        Vector::new(difference(Vector::new(h, 0.0, 0.0)), difference(Vector::new(0.0, h, 0.0)),
+                    difference(Vector::new(0.0, 0.0, h)))
+            .normalize()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
+
+impl Drawable for SdfObject {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for SdfObject {
+    /// Distance fields have no parametrization, the texture is projected along the y axis. Solid
+    /// textures usually fit them better.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        Point2D {
+            x: hit_point.get_x(),
+            y: hit_point.get_z(),
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+
+    fn material() -> Material {
+        Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0)
+    }
+
+    fn bounds(size: f64) -> BoundingBox {
+        BoundingBox::new(Vector::new(-size, -size, -size), Vector::new(size, size, size))
+    }
+
+    #[test]
+    fn test_closure() {
+        let sphere = SdfObject::new(Box::new(|point: &Vector| point.euclidian_distance() - 1.0), bounds(1.0),
+                                    material());
+        let ray = Ray::from(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let distance = sphere.intersect(&ray).unwrap();
+        assert!((distance - 4.0).abs() < 1e-3);
+        let normal = sphere.surface_normal(&Vector::new(0.0, 0.0, 1.0));
+        assert!(normal.minus(&Vector::new(0.0, 0.0, 1.0)).euclidian_distance() < 1e-6);
+
+        // leaving the surface does not hit it again, but going through does
+        let outward = Ray::from(Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(sphere.intersect(&outward), None);
+        let inward = Ray::from(Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, 0.0, -1.0));
+        assert!((sphere.intersect(&inward).unwrap() - 2.0).abs() < 1e-3);
+
+        let miss = Ray::from(Vector::new(0.0, 1.01, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(sphere.intersect(&miss), None);
+    }
+
+    #[test]
+    fn test_nodes() {
+        let unit = Box::new(SdfNode::Box { half_size: Vector::new(1.0, 1.0, 1.0) });
+        assert_eq!(unit.distance(&Vector::new(3.0, 0.0, 0.0)), 2.0);
+        assert_eq!(unit.distance(&Vector::zero()), -1.0);
+
+        let repeated = SdfNode::Repeat { period: Vector::new(4.0, 0.0, 0.0), node: unit };
+        assert_eq!(repeated.distance(&Vector::new(8.0, 0.0, 0.0)), -1.0);
+        assert_eq!(repeated.distance(&Vector::new(6.0, 0.0, 0.0)), 1.0);
+
+        let spheres = || (Box::new(SdfNode::Sphere { radius: 1.0 }),
+                          Box::new(SdfNode::Translate { offset: Vector::new(3.0, 0.0, 0.0),
+                                                        node: Box::new(SdfNode::Sphere { radius: 1.0 }) }));
+        let (left, right) = spheres();
+        let union = SdfNode::Union(left, right);
+        let (left, right) = spheres();
+        let smooth = SdfNode::SmoothUnion { left: left, right: right, smoothness: 1.0 };
+        // the blend fills the gap between the spheres
+        let between = Vector::new(1.5, 0.0, 0.0);
+        assert_eq!(union.distance(&between), 0.5);
+        assert!(smooth.distance(&between) < 0.5);
+        assert_eq!(smooth.distance(&Vector::new(-3.0, 0.0, 0.0)), 2.0);
+    }
+
+    #[test]
+    fn test_twisted_box() {
+        let twisted = SdfNode::Twist {
+            rate: 90.0,
+            node: Box::new(SdfNode::Box { half_size: Vector::new(1.0, 2.0, 0.2) }),
+        };
+        let radius = 1.0f64.hypot(0.2);
+        assert_eq!(twisted.bounding_box(), BoundingBox::new(Vector::new(-radius, -2.0, -radius),
+                                                            Vector::new(radius, 2.0, radius)));
+        let mut object = SdfObject::new(Box::new(twisted), bounds(2.5), material());
+        object.set_step_scale(0.5);
+        // a quarter turn up the box is thin along x
+        let ray = Ray::from(Vector::new(5.0, 1.0, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert!((object.intersect(&ray).unwrap() - 4.8).abs() < 1e-3);
+    }
+}
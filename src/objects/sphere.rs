@@ -1,12 +1,15 @@
 use crate::vector::Vector;
-use crate::base::{Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use crate::base::{BoundingBox, Color, Intersectable, Intersection, Ray, Drawable, Point2D, Textureable};
 use std::f64::consts::PI;
 use crate::material::Material;
+use crate::objects::{moved, moved_hit};
 
 pub struct Sphere {
     center: Vector,
     radius: f64,
     material: Material,
+    /// Center at time 1 for a moving sphere, it starts at `center` at time 0.
+    end_center: Option<Vector>,
 }
 
 impl Sphere {
@@ -14,12 +17,23 @@ impl Sphere {
         Sphere{
             center: center,
             radius: radius,
-            material: material
+            material: material,
+            end_center: None,
         }
     }
     pub fn get_center(&self) -> &Vector {
         &self.center
     }
+    /// Moves the sphere in a straight line, reaching `end_center` at time 1.
+    pub fn set_end_center(&mut self, end_center: Vector) {
+        self.end_center = Some(end_center);
+    }
+    pub fn center_at(&self, time: f64) -> Vector {
+        match &self.end_center {
+            Some(end_center) => moved(&self.center, end_center, time),
+            None => self.center.clone(),
+        }
+    }
     pub fn get_radius(&self) -> f64 {
         self.radius
     }
@@ -28,12 +42,12 @@ impl Sphere {
 impl Intersectable for Sphere {
     fn intersect(&self, ray: &Ray) -> Option<f64> {
         //Create a line segment between the ray origin and the center of the sphere
-        let l: Vector = self.center.minus(ray.get_origin());
+        let l: Vector = self.center_at(ray.get_time()).minus(ray.get_origin());
         //Use l as a hypotenuse and find the length of the adjacent side
         let adj2 = l.dot(ray.get_direction());
         //Find the length-squared of the opposite side
         //This is equivalent to (but faster than) (l.length() * l.length()) - (adj2 * adj2)
-        let d2 = l.dot(&l) - (&adj2 * &adj2);
+        let d2 = l.dot(&l) - (adj2 * adj2);
         //If that length-squared is less than radius squared, the ray intersects the sphere
         let radius2 = self.radius * self.radius;
         if d2 > radius2 {
@@ -48,19 +62,65 @@ impl Intersectable for Sphere {
             return None;
         }
 
-        let distance = if t0 < t1 { t0 } else { t1 };
+        // t0 is behind the origin when the ray starts inside the sphere
+        let distance = if t0 < 0.0 { t1 } else { t0 };
         Some(distance)
     }
 
     fn surface_normal(&self, hit_point: &Vector) -> Vector {
         hit_point.minus(&self.center).normalize()
     }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let l = self.center_at(ray.get_time()).minus(ray.get_origin());
+        let adj = l.dot(ray.get_direction());
+        let d2 = l.dot(&l) - adj * adj;
+        let radius2 = self.radius * self.radius;
+        if d2 > radius2 {
+            return Vec::new();
+        }
+        let thc = (radius2 - d2).sqrt();
+        vec![(adj - thc, adj + thc)]
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let radius = Vector::new(self.radius, self.radius, self.radius);
+        let bounds = BoundingBox::new(self.center.minus(&radius), self.center.plus(&radius));
+        match &self.end_center {
+            Some(end_center) => bounds.union(&BoundingBox::new(end_center.minus(&radius), end_center.plus(&radius))),
+            None => bounds,
+        }
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(4.0 * PI * self.radius * self.radius)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, time: f64) -> Option<(Vector, Vector)> {
+        // uniform in the height, which is uniform in area by Archimedes' hat-box theorem
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let normal = Vector::new(r * phi.cos(), r * phi.sin(), z);
+        Some((self.center_at(time).plus(&normal.factor(self.radius)), normal))
+    }
 }
 
 impl Drawable for Sphere {
     fn get_material(&self) -> &Material {
         &self.material
     }
+
+    fn hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.intersect(ray).map(|distance| self.hit_at(ray, distance))
+    }
+
+    fn hit_at(&self, ray: &Ray, distance: f64) -> Intersection<'_> {
+        match &self.end_center {
+            Some(_) => moved_hit(self, distance, &self.center_at(ray.get_time()).minus(&self.center)),
+            None => Intersection::new(distance, self),
+        }
+    }
 }
 
 impl Textureable for Sphere {
@@ -76,6 +136,28 @@ impl Textureable for Sphere {
 
     fn get_texture_color(&self, hit_point: &Vector) -> Color {
         let tex_coords = self.texture_coords(hit_point);
-        self.material.get_texture().get_color(tex_coords.x, tex_coords.y)
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+
+    #[test]
+    fn test_moving_sphere() {
+        let material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        let mut sphere = Sphere::new(Vector::new(0.0, 0.0, -5.0), 1.0, material);
+        sphere.set_end_center(Vector::new(4.0, 0.0, -5.0));
+        assert_eq!(sphere.bounding_box().get_max(), &Vector::new(5.0, 1.0, -4.0));
+
+        let ray = Ray::from(Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(sphere.intersect(&ray).is_none());
+        let half_way = ray.with_time(0.5);
+        assert_eq!(sphere.intersect(&half_way), Some(4.0));
+        // the normal is found where the sphere is at the time of the ray
+        let intersection = sphere.hit(&half_way).unwrap();
+        assert_eq!(intersection.surface_normal(&Vector::new(2.0, 0.0, -4.0)), Vector::new(0.0, 0.0, 1.0));
     }
 }
@@ -0,0 +1,260 @@
+use crate::vector::Vector;
+use crate::base::{BoundingBox, Color, Intersectable, Ray, Drawable, Point2D, Textureable};
+use std::f64::consts::PI;
+use crate::material::Material;
+use crate::objects::polynomial::solve_quartic;
+use crate::objects::{Roots, MIN_DISTANCE};
+
+/// Crossings closer than this, relative to their distance, are the two halves of a double root.
+const DOUBLE_ROOT_TOLERANCE: f64 = 1e-6;
+
+/// Torus lying in the xz plane around `center`. The tube of `minor_radius` follows a circle of
+/// `major_radius`.
+pub struct Torus {
+    center: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Material,
+}
+
+impl Torus {
+    pub fn new(center: Vector, major_radius: f64, minor_radius: f64, material: Material) -> Torus {
+        Torus {
+            center: center,
+            major_radius: major_radius,
+            minor_radius: minor_radius,
+            material: material,
+        }
+    }
+    pub fn get_center(&self) -> &Vector {
+        &self.center
+    }
+    pub fn get_major_radius(&self) -> f64 {
+        self.major_radius
+    }
+    pub fn get_minor_radius(&self) -> f64 {
+        self.minor_radius
+    }
+    /// Sorted distances at which the ray crosses the surface.
+    pub fn crossings(&self, ray: &Ray) -> Roots {
+        let mut origin = ray.get_origin().minus(&self.center);
+        let direction = ray.get_direction();
+
+        // Starting the quartic close to the torus keeps its coefficients small, which matters for
+        // the precision of the roots of far away rays.
+        let bound = self.major_radius + self.minor_radius;
+        let adj = -origin.dot(direction);
+        let d2 = origin.dot(&origin) - adj * adj;
+        if d2 > bound * bound {
+            return Roots::new();
+        }
+        let start = (adj - (bound * bound - d2).sqrt()).max(0.0);
+        origin = origin.plus(&direction.factor(start));
+
+        let (r2, a2) = (self.major_radius * self.major_radius, self.minor_radius * self.minor_radius);
+        let (ox, oz) = (origin.get_x(), origin.get_z());
+        let (dx, dz) = (direction.get_x(), direction.get_z());
+        // (|o + t d|² + R² - r²)² = 4 R² ((ox + t dx)² + (oz + t dz)²)
+        let a = direction.dot(direction);
+        let b = 2.0 * origin.dot(direction);
+        let c = origin.dot(&origin) + r2 - a2;
+        solve_quartic(a * a,
+                      2.0 * a * b,
+                      b * b + 2.0 * a * c - 4.0 * r2 * (dx * dx + dz * dz),
+                      2.0 * b * c - 8.0 * r2 * (ox * dx + oz * dz),
+                      c * c - 4.0 * r2 * (ox * ox + oz * oz))
+            .into_iter()
+            .map(|t| t + start)
+            .collect()
+    }
+    /// Pairs the sorted crossings of the ray into spans inside the tube. A ray tangent to the tube
+    /// touches it in a double root, which comes back as two close crossings or as a single one, and
+    /// is left out so that the others pair up as entries and exits.
+    fn spans(&self, ray: &Ray, crossings: &[f64]) -> Vec<(f64, f64)> {
+        let mut distinct = Roots::new();
+        let mut i = 0;
+        while i < crossings.len() {
+            let close = crossings.get(i + 1)
+                .is_some_and(|&next| next - crossings[i] < DOUBLE_ROOT_TOLERANCE * next.abs().max(1.0));
+            if close {
+                i += 2;
+            } else {
+                distinct.push(crossings[i]);
+                i += 1;
+            }
+        }
+        if distinct.len() % 2 == 1 {
+            // the lone double root is where the ray runs along the surface
+            let direction = ray.get_direction().normalize();
+            let grazing = |t: f64| {
+                let point = ray.get_origin().plus(&ray.get_direction().factor(t));
+                self.surface_normal(&point).dot(&direction).abs()
+            };
+            let tangent = (0..distinct.len())
+                .min_by(|&a, &b| grazing(distinct[a]).partial_cmp(&grazing(distinct[b]))
+                    .unwrap_or(std::cmp::Ordering::Equal))
+                .unwrap_or(0);
+            distinct = distinct.iter().enumerate()
+                .filter(|&(i, _)| i != tangent)
+                .map(|(_, &t)| t)
+                .collect();
+        }
+        distinct.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+}
+
+impl Intersectable for Torus {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        self.crossings(ray).into_iter().find(|&t| t > MIN_DISTANCE)
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        let local = hit_point.minus(&self.center);
+        let ring = Vector::new(local.get_x(), 0.0, local.get_z());
+        if ring.dot(&ring) == 0.0 {
+            return local.normalize();
+        }
+        // points away from the closest point on the circle inside the tube
+        local.minus(&ring.normalize().factor(self.major_radius)).normalize()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let bound = self.major_radius + self.minor_radius;
+        let extent = Vector::new(bound, self.minor_radius, bound);
+        BoundingBox::new(self.center.minus(&extent), self.center.plus(&extent))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        self.spans(ray, &self.crossings(ray))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(4.0 * PI * PI * self.major_radius * self.minor_radius)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        // the outside of the tube is wider than the inside, the angle around it is found by
+        // bisecting the share of the area up to it, R θ + r sin θ
+        let (major, minor) = (self.major_radius, self.minor_radius);
+        let target = 2.0 * PI * major * u;
+        let (mut low, mut high) = (0.0, 2.0 * PI);
+        for _ in 0..48 {
+            let middle = 0.5 * (low + high);
+            if major * middle + minor * middle.sin() < target {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        let theta = 0.5 * (low + high);
+        let phi = 2.0 * PI * v;
+        let ring = Vector::new(phi.cos(), 0.0, phi.sin());
+        let normal = ring.factor(theta.cos()).plus(&Vector::new(0.0, theta.sin(), 0.0));
+        Some((self.center.plus(&ring.factor(major)).plus(&normal.factor(minor)), normal))
+    }
+}
+
+impl Drawable for Torus {
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+impl Textureable for Torus {
+    /// `x` goes around the ring and `y` around the tube.
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let hit_vec = hit_point.minus(&self.center);
+        let phi = hit_vec.get_z().atan2(hit_vec.get_x());
+        let ring_distance = (hit_vec.get_x().powi(2) + hit_vec.get_z().powi(2)).sqrt() - self.major_radius;
+        let theta = hit_vec.get_y().atan2(ring_distance);
+        Point2D {
+            x: (1.0 + phi / PI) * 0.5,
+            y: (1.0 + theta / PI) * 0.5,
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.material.get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::SurfaceType;
+    use crate::objects::assert_samples_on_surface;
+
+    fn torus() -> Torus {
+        Torus::new(Vector::zero(), 2.0, 0.5,
+                   Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0))
+    }
+
+    fn assert_close(distance: Option<f64>, expected: f64) {
+        assert!(distance.is_some_and(|t| (t - expected).abs() < 1e-6), "{:?} != {}", distance, expected);
+    }
+
+    #[test]
+    fn test_intersect() {
+        let torus = torus();
+        let ray = Ray::from(Vector::new(-10.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(torus.crossings(&ray).len(), 4);
+        assert_close(torus.intersect(&ray), 7.5);
+        assert_eq!(torus.surface_normal(&Vector::new(-2.5, 0.0, 0.0)), Vector::new(-1.0, 0.0, 0.0));
+
+        // straight through the hole
+        let hole = Ray::from(Vector::new(0.0, 10.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(torus.intersect(&hole), None);
+        let tube = Ray::from(Vector::new(2.0, 10.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_close(torus.intersect(&tube), 9.5);
+        assert_eq!(torus.surface_normal(&Vector::new(2.0, 0.5, 0.0)), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_from_inside() {
+        let torus = torus();
+        // leaves the tube where sqrt(2² + t²) = 2.5
+        let ray = Ray::from(Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_close(torus.intersect(&ray), 1.5);
+        let inward = Ray::from(Vector::new(2.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_close(torus.intersect(&inward), 0.5);
+    }
+
+    #[test]
+    fn test_grazing() {
+        let torus = torus();
+        // touching the top of the tube
+        let top = Ray::from(Vector::new(-10.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(torus.intersect(&top).is_some_and(|t| (t - 8.0).abs() < 1e-6));
+        let above = Ray::from(Vector::new(-10.0, 0.5 + 1e-6, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(torus.intersect(&above), None);
+    }
+
+    #[test]
+    fn test_tangent_intervals() {
+        let torus = torus();
+        // only touching the tube is not being inside it
+        let top = Ray::from(Vector::new(-10.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(torus.intervals(&top), Vec::new());
+
+        // touching the inner side of the tube halfway through it
+        let inner = Ray::from(Vector::new(1.5, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let intervals = torus.intervals(&inner);
+        assert_eq!(intervals.len(), 1, "{:?}", intervals);
+        assert!((intervals[0].0 - 8.0).abs() < 1e-6 && (intervals[0].1 - 12.0).abs() < 1e-6, "{:?}", intervals);
+        // also when the double root comes back only once
+        assert_eq!(torus.spans(&inner, &[8.0, 10.0, 12.0]), vec![(8.0, 12.0)]);
+        assert_eq!(torus.spans(&inner, &[10.0, 11.5, 12.0]), vec![(11.5, 12.0)]);
+    }
+    #[test]
+    fn test_sampled_surface() {
+        let torus = torus();
+        assert!((torus.surface_area().unwrap() - 4.0 * PI * PI).abs() < 1e-12);
+        assert_samples_on_surface(&torus);
+        // the outer half of the tube is sampled more often than the inner one
+        let outside = (0..100)
+            .filter(|&i| torus.sample_surface((i as f64 + 0.5) / 100.0, 0.0, 0.0).unwrap().1.get_x() > 0.0)
+            .count();
+        assert_eq!(outside, 58);
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use crate::base::{BoundingBox, Color, Drawable, Intersectable, Point2D, Ray, Textureable};
+use crate::material::Material;
+use crate::objects::mesh::{Face, Mesh};
+use crate::objects::MIN_DISTANCE;
+use crate::vector::Vector;
+
+const EPSILON: f64 = 1e-12;
+
+pub struct Triangle {
+    mesh: Arc<Mesh>,
+    face: usize,
+}
+
+impl Triangle {
+    pub fn new(mesh: Arc<Mesh>, face: usize) -> Triangle {
+        Triangle {
+            mesh: mesh,
+            face: face,
+        }
+    }
+    fn get_face(&self) -> &Face {
+        &self.mesh.get_faces()[self.face]
+    }
+    fn get_vertices(&self) -> [&Vector; 3] {
+        let [a, b, c] = self.get_face().positions;
+        [self.mesh.get_position(a), self.mesh.get_position(b), self.mesh.get_position(c)]
+    }
+    /// Barycentric weights of the vertices for a point lying in the plane of the triangle.
+    pub fn barycentric(&self, point: &Vector) -> [f64; 3] {
+        let [p0, p1, p2] = self.get_vertices();
+        let e1 = p1.minus(p0);
+        let e2 = p2.minus(p0);
+        let p = point.minus(p0);
+
+        let d00 = e1.dot(&e1);
+        let d01 = e1.dot(&e2);
+        let d11 = e2.dot(&e2);
+        let d20 = p.dot(&e1);
+        let d21 = p.dot(&e2);
+        let denominator = d00 * d11 - d01 * d01;
+
+        let v = (d11 * d20 - d01 * d21) / denominator;
+        let w = (d00 * d21 - d01 * d20) / denominator;
+        [1.0 - v - w, v, w]
+    }
+    pub fn geometric_normal(&self) -> Vector {
+        let [p0, p1, p2] = self.get_vertices();
+        p1.minus(p0).cross(&p2.minus(p0)).normalize()
+    }
+}
+
+impl Intersectable for Triangle {
+    fn intersect(&self, ray: &Ray) -> Option<f64> {
+        // Möller–Trumbore
+        let [p0, p1, p2] = self.get_vertices();
+        let e1 = p1.minus(p0);
+        let e2 = p2.minus(p0);
+
+        let p = ray.get_direction().cross(&e2);
+        let determinant = e1.dot(&p);
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+
+        let t = ray.get_origin().minus(p0);
+        let u = t.dot(&p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = t.cross(&e1);
+        let v = ray.get_direction().dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = e2.dot(&q) * inverse;
+        if distance > MIN_DISTANCE {
+            return Some(distance);
+        }
+        None
+    }
+
+    fn surface_normal(&self, hit_point: &Vector) -> Vector {
+        match self.get_face().normals {
+            Some([a, b, c]) => {
+                let [wa, wb, wc] = self.barycentric(hit_point);
+                self.mesh.get_normal(a).factor(wa)
+                    .plus(&self.mesh.get_normal(b).factor(wb))
+                    .plus(&self.mesh.get_normal(c).factor(wc))
+                    .normalize()
+            }
+            None => self.geometric_normal(),
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(&self.get_vertices())
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        let [p0, p1, p2] = self.get_vertices();
+        Some(p1.minus(p0).cross(&p2.minus(p0)).euclidian_distance() / 2.0)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64, _time: f64) -> Option<(Vector, Vector)> {
+        // folds the unit square onto the triangle without bunching points at a corner
+        let s = u.sqrt();
+        let (b1, b2) = (s * (1.0 - v), s * v);
+        let [p0, p1, p2] = self.get_vertices();
+        let point = p0.plus(&p1.minus(p0).factor(b1)).plus(&p2.minus(p0).factor(b2));
+        Some((point, self.geometric_normal()))
+    }
+}
+
+impl Drawable for Triangle {
+    fn get_material(&self) -> &Material {
+        self.mesh.get_material()
+    }
+}
+
+impl Textureable for Triangle {
+    fn texture_coords(&self, hit_point: &Vector) -> Point2D {
+        let [wa, wb, wc] = self.barycentric(hit_point);
+        match self.get_face().uvs {
+            Some([a, b, c]) => {
+                let (ta, tb, tc) = (self.mesh.get_uv(a), self.mesh.get_uv(b), self.mesh.get_uv(c));
+                Point2D {
+                    x: ta.x * wa + tb.x * wb + tc.x * wc,
+                    y: ta.y * wa + tb.y * wb + tc.y * wc,
+                }
+            }
+            None => Point2D {
+                x: wb,
+                y: wc,
+            },
+        }
+    }
+
+    fn get_texture_color(&self, hit_point: &Vector) -> Color {
+        let tex_coords = self.texture_coords(hit_point);
+        self.get_material().get_texture().get_color_at(hit_point, &tex_coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Color;
+    use crate::material::SurfaceType;
+
+    fn triangle() -> Triangle {
+        let mesh = Mesh::new(
+            vec![Vector::new(0.0, 0.0, -1.0), Vector::new(1.0, 0.0, -1.0), Vector::new(0.0, 1.0, -1.0)],
+            vec![],
+            vec![],
+            vec![Face { positions: [0, 1, 2], normals: None, uvs: None }],
+            Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0),
+        );
+        mesh.into_triangles().pop().unwrap()
+    }
+
+    #[test]
+    fn test_intersect() {
+        let triangle = triangle();
+        let hit = Ray::from(Vector::new(0.25, 0.25, 0.0), Vector::new(0.0, 0.0, -1.0));
+        let miss = Ray::from(Vector::new(0.75, 0.75, 0.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert_eq!(triangle.intersect(&hit), Some(1.0));
+        assert_eq!(triangle.intersect(&miss), None);
+    }
+
+    #[test]
+    fn test_ray_leaving_surface() {
+        let triangle = triangle();
+        // rays starting on the triangle, or off it by no more than the shadow bias, do not hit it again
+        let leaving = Ray::from(Vector::new(0.25, 0.25, -1.0), Vector::new(0.3, 0.2, 1.0).normalize());
+        let offset = Ray::from(Vector::new(0.25, 0.25, -1.0 + 1e-13), Vector::new(0.0, 0.0, -1.0));
+
+        assert_eq!(triangle.intersect(&leaving), None);
+        assert_eq!(triangle.intersect(&offset), None);
+    }
+
+    #[test]
+    fn test_barycentric_coords() {
+        let triangle = triangle();
+        let coords = triangle.texture_coords(&Vector::new(0.25, 0.5, -1.0));
+
+        assert!((coords.x - 0.25).abs() < 1e-9);
+        assert!((coords.y - 0.5).abs() < 1e-9);
+        assert_eq!(triangle.surface_normal(&Vector::new(0.25, 0.5, -1.0)), Vector::new(0.0, 0.0, 1.0));
+    }
+}
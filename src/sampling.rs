@@ -0,0 +1,217 @@
+//! Random numbers and sample placement for anti-aliasing and light transport.
+
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+use crate::vector::Vector;
+
+/// SplitMix64 generator. It is tiny and every pixel gets its own, seeded from the pixel
+/// coordinates, so renders are reproducible no matter which thread renders which pixel.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: seed,
+        }
+    }
+    pub fn for_pixel(seed: u64, x: u32, y: u32) -> Rng {
+        let mut rng = Rng::new(seed ^ ((x as u64) << 32 | y as u64));
+        // decorrelate neighbouring pixels, whose seeds differ in a single bit
+        rng.next_u64();
+        rng
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    /// Uniform number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+    /// Puts the items in a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Placement of the samples taken for a single pixel.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplePattern {
+    /// Samples at the centres of a regular grid.
+    Regular,
+    /// One random sample inside every cell of a regular grid.
+    Jittered,
+    /// Uniformly random samples.
+    Random,
+}
+
+impl SamplePattern {
+    /// Generates `count` points in `[0, 1)²`. The grid based patterns split the square into
+    /// `count` cells, in as many rows as the largest divisor of `count` that is not above its
+    /// square root, so square counts give a square grid.
+    pub fn generate(&self, count: u32, rng: &mut Rng) -> Vec<(f64, f64)> {
+        let count = count.max(1);
+        match self {
+            SamplePattern::Random => (0..count).map(|_| (rng.next_f64(), rng.next_f64())).collect(),
+            SamplePattern::Regular | SamplePattern::Jittered => {
+                let rows = (1..=(count as f64).sqrt().floor() as u32).rev()
+                    .find(|&rows| count.is_multiple_of(rows))
+                    .unwrap_or(1);
+                let columns = count / rows;
+                let mut points = Vec::with_capacity(count as usize);
+                for j in 0..rows {
+                    for i in 0..columns {
+                        let (u, v) = if *self == SamplePattern::Jittered {
+                            (rng.next_f64(), rng.next_f64())
+                        } else {
+                            (0.5, 0.5)
+                        };
+                        points.push(((i as f64 + u) / columns as f64, (j as f64 + v) / rows as f64));
+                    }
+                }
+                points
+            }
+        }
+    }
+}
+
+/// Pixel reconstruction filter. Samples are spread over the filter support around the pixel
+/// centre and averaged with the filter weights.
+#[derive(PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    Box,
+    Tent,
+    Gaussian,
+    /// Mitchell-Netravali with B = C = 1/3.
+    Mitchell,
+}
+
+impl Filter {
+    /// Half width of the filter support in pixels.
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+    /// One dimensional filter weight at offset `x` pixels from the centre.
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match self {
+            Filter::Box => if x <= 0.5 { 1.0 } else { 0.0 },
+            Filter::Tent => (1.0 - x).max(0.0),
+            Filter::Gaussian => {
+                let alpha = 2.0;
+                let radius = self.radius();
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)) / 6.0
+                } else if x < 2.0 {
+                    ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Maps `(u, v)` in `[0, 1)²` to a direction in the hemisphere around the unit `normal`, with a
+/// density proportional to the cosine of the angle to the normal.
+pub fn cosine_hemisphere(normal: &Vector, u: f64, v: f64) -> Vector {
+    let (tangent, bitangent) = normal.orthonormal_basis();
+    let r = u.sqrt();
+    let phi = 2.0 * PI * v;
+    tangent.factor(r * phi.cos())
+        .plus(&bitangent.factor(r * phi.sin()))
+        .plus(&normal.factor((1.0 - u).max(0.0).sqrt()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_stay_in_unit_square() {
+        let mut rng = Rng::new(7);
+        for pattern in [SamplePattern::Regular, SamplePattern::Jittered, SamplePattern::Random].iter() {
+            let points = pattern.generate(16, &mut rng);
+            assert_eq!(points.len(), 16);
+            assert!(points.iter().all(|&(u, v)| (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)));
+        }
+        assert_eq!(SamplePattern::Regular.generate(1, &mut rng), vec![(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn test_grids_keep_the_count() {
+        let mut rng = Rng::new(7);
+        for count in [2, 7, 8, 12, 15].iter() {
+            assert_eq!(SamplePattern::Jittered.generate(*count, &mut rng).len(), *count as usize);
+        }
+        // 8 samples are a grid of 2 rows and 4 columns
+        let points = SamplePattern::Regular.generate(8, &mut rng);
+        assert_eq!(points[0], (0.125, 0.25));
+        assert_eq!(points[7], (0.875, 0.75));
+    }
+
+    #[test]
+    fn test_filters_vanish_outside_support() {
+        for filter in [Filter::Box, Filter::Tent, Filter::Gaussian, Filter::Mitchell].iter() {
+            assert!(filter.evaluate(0.0) > 0.0);
+            assert_eq!(filter.evaluate(filter.radius() + 0.01), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_cosine_hemisphere() {
+        let normal = Vector::new(0.0, 0.0, 1.0);
+        let mut rng = Rng::new(3);
+        let mut mean_cos = 0.0;
+        for _ in 0..10000 {
+            let direction = cosine_hemisphere(&normal, rng.next_f64(), rng.next_f64());
+            assert!((direction.euclidian_distance() - 1.0).abs() < 1e-9);
+            assert!(direction.dot(&normal) >= 0.0);
+            mean_cos += direction.dot(&normal) / 10000.0;
+        }
+        // E[cos] is 2/3 for a cosine weighted hemisphere
+        assert!((mean_cos - 2.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let mut a = Rng::for_pixel(1, 10, 20);
+        let mut b = Rng::for_pixel(1, 10, 20);
+        let mut c = Rng::for_pixel(1, 11, 20);
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(a.next_u64(), c.next_u64());
+    }
+
+    #[test]
+    fn test_shuffle_keeps_the_items() {
+        let mut items: Vec<u32> = (0..20).collect();
+        Rng::new(3).shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<u32>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<u32>>());
+    }
+}
@@ -1,152 +1,580 @@
-use image::{DynamicImage, GenericImage, Rgba};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+
+use image::DynamicImage;
 
 use crate::base::{Color, Drawable, Intersection, Ray};
-use crate::lighting::Lighting;
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::environment::{Environment, SolidEnvironment};
+use crate::framebuffer::Framebuffer;
+use crate::integrator::whitted::WhittedIntegrator;
+use crate::integrator::Integrator;
+use crate::lighting::emissive::EmissiveLight;
+use crate::lighting::environment::EnvironmentLight;
+use crate::lighting::{LightSample, Lighting};
 use crate::material::SurfaceType;
+use crate::objects::mesh::Mesh;
+use crate::sampling::{Rng, SamplePattern};
+use crate::settings::RenderSettings;
+use crate::vector::Vector;
 
-const SHADOW_BIAS: f64 = 1e-13;
-const MAX_RECURSION_DEPTH: u32 = 5;
+/// Offset of secondary rays from the surface they start on, to keep them from hitting it again.
+pub const SHADOW_BIAS: f64 = 1e-13;
+const MAX_SHADOW_OCCLUDERS: u32 = 16;
 
-const SKY_COLOR: [u8; 4] = [128, 128, 255, 255];
+const SKY_COLOR: [f64; 3] = [0.216, 0.216, 1.0];
 
 pub struct Scene {
-    width: u32,
-    height: u32,
-    fov: f64,
-    objects: Vec<Box<dyn Drawable>>,
+    camera: Camera,
+    objects: Vec<Arc<dyn Drawable>>,
     lights: Vec<Box<dyn Lighting>>,
+    /// Addresses of the objects that glow and are sampled by an `EmissiveLight`.
+    emitters: HashSet<usize>,
+    environment: Arc<dyn Environment>,
+    /// Positions of the lights that belong to the environment among the lights, its
+    /// `EnvironmentLight` and the sun of a sky, which are replaced along with it.
+    environment_lights: Vec<usize>,
+    /// Whether the environment itself is sampled by an `EnvironmentLight`.
+    environment_sampled: bool,
+    bvh: OnceLock<Bvh>,
+    settings: RenderSettings,
+    integrator: Box<dyn Integrator>,
 }
 
-fn to_rgba(col: &Color) -> Rgba<u8> {
-    let [r, g, b] = col.get();
-    Rgba([r, g, b, 255])
+/// Rectangular block of pixels rendered by a single worker.
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
 }
 
 impl Scene {
-    pub fn new(width: u32, height: u32, fov: f64) -> Scene {
+    pub fn new(camera: Camera) -> Scene {
         Scene {
-            width: width,
-            height: height,
-            fov: fov,
+            camera: camera,
             objects: Vec::new(),
             lights: Vec::new(),
+            emitters: HashSet::new(),
+            environment: Arc::new(SolidEnvironment::new(Color::from_array(SKY_COLOR))),
+            environment_lights: Vec::new(),
+            environment_sampled: false,
+            bvh: OnceLock::new(),
+            settings: RenderSettings::default(),
+            integrator: Box::new(WhittedIntegrator::default()),
         }
     }
+    /// Renders the image and tone maps it for display, see `render_hdr`.
     pub fn render(&self) -> DynamicImage {
+        self.render_hdr().to_image(self.settings.tone_mapping, self.settings.exposure)
+    }
+    /// Renders the linear colors with `RenderSettings::threads` workers pulling tiles off a shared
+    /// counter. Every pixel is computed independently, so the result does not depend on the
+    /// number of threads.
+    pub fn render_hdr(&self) -> Framebuffer {
         let start_time = std::time::SystemTime::now();
 
-        let mut image = DynamicImage::new_rgba8(self.width, self.height);
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let ray = Ray::new(x, y, self);
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
+        let thread_count = self.settings.get_thread_count().min(tiles.len()).max(1);
 
-                let trace = self.trace(&ray);
-                if trace.is_some() {
-                    image.put_pixel(x, y, to_rgba(&self.get_color(&ray, &trace.unwrap(), 1)));
-                } else {
-                    image.put_pixel(x, y, Rgba(SKY_COLOR));
+        let worker = || {
+            let mut rendered = Vec::new();
+            loop {
+                let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                match tiles.get(index) {
+                    Some(tile) => rendered.push((tile, self.render_tile(tile))),
+                    None => return rendered,
                 }
             }
+        };
+        let rendered = if thread_count == 1 {
+            worker()
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = (0..thread_count).map(|_| scope.spawn(worker)).collect();
+                handles.into_iter()
+                    .flat_map(|handle| handle.join().expect("render worker panicked"))
+                    .collect()
+            })
+        };
+
+        let mut framebuffer = Framebuffer::new(self.settings.width, self.settings.height);
+        for (tile, pixels) in rendered {
+            for (i, pixel) in pixels.into_iter().enumerate() {
+                let i = i as u32;
+                framebuffer.put_pixel(tile.x + i % tile.width, tile.y + i / tile.width, pixel);
+            }
         }
         let duration = std::time::SystemTime::now().duration_since(start_time);
-        println!("Rendered the image in {:?}", duration);
-        image
+        println!("Rendered the image in {:?} using {} threads", duration, thread_count);
+        framebuffer
+    }
+    fn tiles(&self) -> Vec<Tile> {
+        let size = self.settings.tile_size.max(1);
+        let (width, height) = (self.settings.width, self.settings.height);
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(size as usize) {
+            for x in (0..width).step_by(size as usize) {
+                tiles.push(Tile {
+                    x: x,
+                    y: y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                });
+            }
+        }
+        tiles
+    }
+    /// Renders the pixels of a tile in row-major order.
+    fn render_tile(&self, tile: &Tile) -> Vec<Color> {
+        let mut pixels = Vec::with_capacity((tile.width * tile.height) as usize);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                pixels.push(self.render_pixel(x, y));
+            }
+        }
+        pixels
+    }
+    /// Averages the samples of a pixel, spread over the support of the reconstruction filter and
+    /// weighted by it.
+    fn render_pixel(&self, x: u32, y: u32) -> Color {
+        let settings = &self.settings;
+        let mut rng = Rng::for_pixel(settings.seed, x, y);
+        let radius = settings.filter.radius();
+
+        let points = settings.pattern.generate(settings.samples, &mut rng);
+        // the lens is sampled with a pattern of its own, shuffled so that the position in the
+        // pixel and on the lens are not correlated
+        let lens_points = if self.camera.get_aperture() > 0.0 {
+            let mut lens_points = settings.pattern.generate(settings.samples, &mut rng);
+            rng.shuffle(&mut lens_points);
+            lens_points
+        } else {
+            vec![(0.5, 0.5); points.len()]
+        };
+        // one time in each of as many equal parts of the shutter interval, shuffled likewise
+        let times = if self.camera.has_motion_blur() {
+            let count = points.len();
+            let mut times: Vec<f64> = (0..count).map(|i| (i as f64 + rng.next_f64()) / count as f64).collect();
+            rng.shuffle(&mut times);
+            times
+        } else {
+            vec![0.0; points.len()]
+        };
+
+        let mut color = Color::black();
+        let mut total_weight = 0.0;
+        // the negative lobes of the Mitchell filter can cancel out the few samples of a pixel, which
+        // is then averaged with the magnitudes of the weights instead
+        let mut absolute_color = Color::black();
+        let mut absolute_weight = 0.0;
+        for ((&(u, v), &(lens_u, lens_v)), &time) in points.iter().zip(lens_points.iter()).zip(times.iter()) {
+            let dx = (u * 2.0 - 1.0) * radius;
+            let dy = (v * 2.0 - 1.0) * radius;
+            let weight = settings.filter.evaluate(dx) * settings.filter.evaluate(dy);
+            if weight == 0.0 {
+                continue;
+            }
+            let ray = self.camera.get_lens_ray((x as f64 + 0.5 + dx) / settings.width as f64,
+                                               (y as f64 + 0.5 + dy) / settings.height as f64, lens_u, lens_v);
+            // outside the image circle of a fisheye there is nothing to see
+            let radiance = match ray {
+                Some(ray) => self.integrator.radiance(self, &ray.with_time(self.camera.shutter_time(time)), &mut rng),
+                None => Color::black(),
+            };
+            color = color + radiance.factor(weight);
+            total_weight += weight;
+            absolute_color = absolute_color + radiance.factor(weight.abs());
+            absolute_weight += weight.abs();
+        }
+        if total_weight > 0.0 {
+            color.factor(1.0 / total_weight)
+        } else if absolute_weight > 0.0 {
+            absolute_color.factor(1.0 / absolute_weight)
+        } else {
+            Color::black()
+        }
     }
+    /// Adds an object, glowing objects whose surface can be sampled also become a light.
     pub fn add_object(&mut self, obj: Box<dyn Drawable>) {
+        let obj: Arc<dyn Drawable> = Arc::from(obj);
+        if obj.surface_area().is_some() && obj.get_material().is_emissive() {
+            self.add_emitters(vec![obj.clone()]);
+        }
         self.objects.push(obj);
+        self.bvh = OnceLock::new();
+    }
+    /// Adds the triangles of the mesh, a glowing mesh lights the scene as a whole.
+    pub fn add_mesh(&mut self, mesh: Mesh) {
+        let triangles: Vec<Arc<dyn Drawable>> = mesh.into_triangles().into_iter()
+            .map(|triangle| Arc::new(triangle) as Arc<dyn Drawable>)
+            .collect();
+        if triangles.first().is_some_and(|triangle| triangle.get_material().is_emissive()) {
+            self.add_emitters(triangles.clone());
+        }
+        self.objects.extend(triangles);
+        self.bvh = OnceLock::new();
+    }
+    fn add_emitters(&mut self, shapes: Vec<Arc<dyn Drawable>>) {
+        let material = shapes[0].get_material();
+        let light = EmissiveLight::new(shapes.clone(), material.get_emission().clone(), material.get_light_samples());
+        for shape in shapes.iter() {
+            self.emitters.insert(Arc::as_ptr(shape) as *const () as usize);
+        }
+        self.lights.push(Box::new(light));
+    }
+    /// Whether the emission of the object is already gathered by sampling the lights, which
+    /// integrators following random directions have to skip when they run into it.
+    pub fn is_light_source(&self, object: &dyn Drawable) -> bool {
+        self.emitters.contains(&(object as *const dyn Drawable as *const () as usize))
     }
     pub fn add_light(&mut self, light: Box<dyn Lighting>) { self.lights.push(light); }
-    pub fn get_width(&self) -> u32 {
-        self.width
+    pub fn get_lights(&self) -> &[Box<dyn Lighting>] {
+        &self.lights
+    }
+    /// Color seen by rays going in `direction` that do not hit anything.
+    pub fn get_background(&self, direction: &Vector) -> Color {
+        self.environment.radiance(&direction.normalize())
+    }
+    /// Replaces the plain sky around the scene. With `light_samples`, the environment also lights
+    /// the scene through that many shadow rays, aimed mostly at its bright parts.
+    /// The lights of the previous environment are removed.
+    pub fn set_environment(&mut self, environment: Arc<dyn Environment>, light_samples: Option<u32>) {
+        // the positions are ascending, removing from the back keeps the others valid
+        for index in self.environment_lights.drain(..).rev() {
+            self.lights.remove(index);
+        }
+        self.environment_sampled = light_samples.is_some();
+        if let Some(samples) = light_samples {
+            self.add_environment_light(Box::new(EnvironmentLight::new(environment.clone(), samples)));
+        }
+        self.environment = environment;
+    }
+    /// Adds a light that is part of the current environment, like the sun of a sky. It goes away
+    /// when the environment is replaced.
+    pub fn add_environment_light(&mut self, light: Box<dyn Lighting>) {
+        self.environment_lights.push(self.lights.len());
+        self.lights.push(light);
+    }
+    /// Whether the light of the environment is already gathered by sampling the lights, see
+    /// `is_light_source`.
+    pub fn is_environment_sampled(&self) -> bool {
+        self.environment_sampled
+    }
+    /// Selects how the light along camera rays is computed, `WhittedIntegrator` by default.
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+    /// Replaces the render settings, keeping the camera aspect ratio in sync with the image size.
+    pub fn set_settings(&mut self, settings: RenderSettings) {
+        self.settings = settings;
+        self.camera.set_aspect_ratio(self.settings.get_aspect_ratio());
+    }
+    pub fn get_settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+    /// Changes the size of the rendered image, keeping the camera aspect ratio in sync.
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.settings.width = width;
+        self.settings.height = height;
+        self.camera.set_aspect_ratio(self.settings.get_aspect_ratio());
     }
-    pub fn get_height(&self) -> u32 {
-        self.height
+    pub fn get_camera(&self) -> &Camera {
+        &self.camera
     }
-    pub fn get_fov(&self) -> f64 {
-        self.fov
+    pub fn get_camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
     }
-    pub fn trace(&self, ray: &Ray) -> Option<Intersection> {
-        let mut objs = Vec::new();
-        for s in self.objects.iter() {
-            let distance = s.intersect(ray);
-            if distance.is_some() {
-                objs.push(Intersection::new(distance.unwrap(), s));
+    /// Finds the closest object hit by the ray. The acceleration structure is built on first use
+    /// after objects were added.
+    pub fn trace(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.bvh.get_or_init(|| Bvh::new(&self.objects))
+            .trace(ray, &self.objects)
+    }
+    /// Light arriving at the point in the color of the light, averaged over the shadow rays sent
+    /// towards the samples on the light at `time`. Partially blocked area lights give soft shadows.
+    pub fn direct_light(&self, light: &dyn Lighting, hit_point: &Vector, surface_normal: &Vector, glossiness: f64,
+                        time: f64, rng: &mut Rng) -> Color {
+        let samples = self.light_samples(light, hit_point, surface_normal, time, rng);
+        self.arriving_light(light, &samples, surface_normal, glossiness)
+    }
+    /// Light reflected towards the viewer, with `reflectance` giving the fraction reflected for
+    /// light arriving from a direction. Like for `direct_light`, a white Lambertian surface reflects
+    /// the intensity of the light times the cosine, so `reflectance` is π times the BRDF.
+    pub fn reflected_light<F>(&self, light: &dyn Lighting, hit_point: &Vector, surface_normal: &Vector, time: f64,
+                              rng: &mut Rng, reflectance: F) -> Color
+        where F: Fn(&Vector) -> Color {
+        let samples = self.light_samples(light, hit_point, surface_normal, time, rng);
+        self.reflected_from(light, &samples, surface_normal, reflectance)
+    }
+    /// `direct_light` from samples that were already traced with `light_samples`, so that several
+    /// terms of the shading can share the same shadow rays.
+    pub fn arriving_light(&self, light: &dyn Lighting, samples: &[LightSample], surface_normal: &Vector,
+                          glossiness: f64) -> Color {
+        let normal = surface_normal.normalize();
+        let mut arriving = Color::black();
+        for sample in samples {
+            let intensity = normal
+                .dot(&sample.get_direction().normalize())
+                .powf(glossiness)
+                .max(0.0) * sample.get_intensity();
+            arriving = arriving + sample.get_tint().factor(intensity);
+        }
+        arriving.times(light.get_color()).factor(1.0 / light.get_sample_count().max(1) as f64)
+    }
+    /// `reflected_light` from samples that were already traced with `light_samples`. Light from
+    /// behind the surface is not reflected.
+    pub fn reflected_from<F>(&self, light: &dyn Lighting, samples: &[LightSample], surface_normal: &Vector,
+                             reflectance: F) -> Color
+        where F: Fn(&Vector) -> Color {
+        let mut reflected = Color::black();
+        for sample in samples {
+            let to_light = sample.get_direction().normalize();
+            let cos = surface_normal.dot(&to_light);
+            if cos <= 0.0 {
+                continue;
             }
+            reflected = reflected + reflectance(&to_light).times(sample.get_tint()).factor(cos * sample.get_intensity());
         }
-        objs.into_iter().min_by(|i1, i2| i1.get_distance().partial_cmp(&i2.get_distance()).unwrap())
+        reflected.times(light.get_color()).factor(1.0 / light.get_sample_count().max(1) as f64)
     }
-    fn get_color(&self, ray: &Ray, intersection: &Intersection, depth: u32) -> Color {
-        let hit_point = ray
-            .get_origin()
-            .plus(&ray.get_direction()
-                .factor(intersection.get_distance()));
-        let surface_normal = intersection.get_object().surface_normal(&hit_point);
+    /// Samples on the light that are not completely blocked from the point, with their intensity
+    /// reduced by the transparent objects in between. Exactly `get_sample_count()` points on the
+    /// light are tried, so results have to be averaged over that count, blocked samples are left
+    /// out.
+    pub fn light_samples(&self, light: &dyn Lighting, hit_point: &Vector, surface_normal: &Vector, time: f64,
+                         rng: &mut Rng) -> Vec<LightSample> {
+        let count = light.get_sample_count().max(1);
+        let points = if count == 1 {
+            vec![(0.5, 0.5)]
+        } else {
+            SamplePattern::Jittered.generate(count, rng)
+        };
+        let origin = hit_point.clone() + surface_normal.factor(SHADOW_BIAS);
 
-        let mut color = [0_f64, 0.0, 0.0];
-
-        for light in self.lights.iter() {
-            let direction_to_light = light.get_direction_to_light(&hit_point);
-            let direction_to_light_norm = direction_to_light.normalize();
+        let mut samples = Vec::new();
+        for &(u, v) in points.iter() {
+            let sample = light.sample(hit_point, u, v, time);
+            if sample.get_intensity() <= 0.0 {
+                continue;
+            }
+            let direction_to_light = sample.get_direction();
 
-            // calculate if the point is a shadow
-            let shadow_ray = Ray::from(
-                hit_point.clone() + (surface_normal.factor(SHADOW_BIAS)),
-                direction_to_light_norm.clone(),
+            // calculate how much of the light reaches the point
+            let transmission = self.shadow_transmission(
+                origin.clone(),
+                &direction_to_light.normalize(),
+                direction_to_light.euclidian_distance(),
+                time,
             );
+            if transmission > 0.0 {
+                samples.push(LightSample::tinted(direction_to_light.clone(), sample.get_intensity() * transmission,
+                                                 sample.get_tint().clone()));
+            }
+        }
+        samples
+    }
+    /// Fraction of light travelling `distance` along `direction` from `origin` at `time` that is not
+    /// blocked. Transparent objects along the way attenuate the light by their transparency,
+    /// anything else blocks it completely.
+    pub fn shadow_transmission(&self, origin: Vector, direction: &Vector, distance: f64, time: f64) -> f64 {
+        let mut transmission = 1.0;
+        let mut origin = origin;
+        let mut remaining = distance;
+        for _ in 0..MAX_SHADOW_OCCLUDERS {
+            let ray = Ray::from(origin, direction.clone()).with_time(time);
+            let intersection = match self.trace(&ray) {
+                Some(intersection) if intersection.get_distance() <= remaining => intersection,
+                _ => return transmission,
+            };
+            match intersection.get_object().get_material().get_surface_type() {
+                SurfaceType::Refractive { transparency, .. } => transmission *= *transparency,
+                _ => return 0.0,
+            }
+            if transmission <= 0.0 {
+                return 0.0;
+            }
+            let step = intersection.get_distance() + SHADOW_BIAS;
+            origin = ray.get_origin().plus(&direction.factor(step));
+            remaining -= step;
+        }
+        0.0
+    }
+}
 
-            let shadow_intersection = self.trace(&shadow_ray);
-            let in_light = shadow_intersection.is_none() ||
-                shadow_intersection.unwrap().get_distance() > direction_to_light.euclidian_distance();
-            ;
-
-            let object = intersection.get_object();
-
-            let light_intensity = if in_light {
-                surface_normal.normalize()
-                    .dot(&direction_to_light_norm)
-                    .powf(object.get_material().get_glossiness())
-                    .max(0.0) * light.get_intensity(&hit_point)
-            } else { 0.0 };
-            let light_reflected = 1.0; // todo: implementiraj
-
-            let light_color = light.get_color().get();
-            let obj_color = object.get_texture_color(&hit_point).get();
-
-            color[0] += light_color[0] as f64 * obj_color[0] as f64 * light_intensity * light_reflected / 255.0;
-            color[1] += light_color[1] as f64 * obj_color[1] as f64 * light_intensity * light_reflected / 255.0;
-            color[2] += light_color[2] as f64 * obj_color[2] as f64 * light_intensity * light_reflected / 255.0;
-
-            if let SurfaceType::Reflective { reflectivity } = object.get_material().get_surface_type() {
-                let reflection_ray = Ray::from_reflection(&surface_normal, ray.get_direction(),
-                                                          &hit_point, SHADOW_BIAS);
-                for elem in color.iter_mut() {
-                    *elem *= 1.0 - *reflectivity;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::rectangle::RectangleLight;
+    use crate::lighting::sphere::SphereLight;
+    use crate::lighting::spherical::SphericalLight;
+    use crate::material::{CheckeredPatternTexture, Material};
+    use crate::objects::plane::Plane;
+    use crate::objects::sphere::Sphere;
+    use crate::sampling::Filter;
 
-                let reflect_color = self.cast_ray(&reflection_ray, depth + 1).get();
-                color[0] += reflect_color[0] as f64 * *reflectivity;
-                color[1] += reflect_color[1] as f64 * *reflectivity;
-                color[2] += reflect_color[2] as f64 * *reflectivity;
-            }
+    fn scene() -> Scene {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 80.0, 70.0 / 45.0);
+        let mut scene = Scene::new(camera);
+        scene.add_object(Box::new(Plane::new(
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, -2.0, 0.0),
+            Material::new(Box::new(CheckeredPatternTexture::new(Color::new(0.4, 0.4, 0.4), 1, 1)),
+                          SurfaceType::Reflective { reflectivity: 0.1 }, 1.0, 1.0),
+        )));
+        scene.add_object(Box::new(Sphere::new(
+            Vector::new(0.0, 0.0, -5.0),
+            1.5,
+            Material::new_constant(Color::new(1.0, 0.0, 0.0), SurfaceType::Reflective { reflectivity: 0.3 }, 1.0, 1.0),
+        )));
+        scene.add_light(Box::new(SphericalLight::new(Vector::new(2.0, 3.0, -2.0), Color::new(1.0, 1.0, 1.0), 20.0)));
+        scene
+    }
 
-//            println!("Light intensity {} light color {:?} obj color {:?} color {:?}", light_intensity,
-//                     light_color, obj_color, color);
-        }
-        let color = color.iter().map(|e| {
-            e.round().max(0.0).min(255.0) as u8
-        }).collect::<Vec<u8>>();
-        Color::new(*color.get(0).unwrap(),
-                   *color.get(1).unwrap(),
-                   *color.get(2).unwrap())
+    #[test]
+    fn test_threads_render_identical_images() {
+        let mut scene = scene();
+        let settings = RenderSettings {
+            width: 70,
+            height: 45,
+            samples: 4,
+            pattern: SamplePattern::Jittered,
+            filter: Filter::Tent,
+            ..RenderSettings::default()
+        };
+        scene.set_settings(RenderSettings { threads: 1, tile_size: 16, ..settings.clone() });
+        let single = scene.render().raw_pixels();
+
+        scene.set_settings(RenderSettings { threads: 4, tile_size: 7, ..settings });
+        let multi = scene.render().raw_pixels();
+
+        assert_eq!(single, multi);
+    }
+
+    #[test]
+    fn test_settings_keep_camera_aspect_ratio() {
+        let mut scene = scene();
+        scene.set_settings(RenderSettings { width: 90, height: 160, ..RenderSettings::default() });
+        assert_eq!(scene.get_camera().get_aspect_ratio(), 90.0 / 160.0);
+        scene.set_resolution(300, 100);
+        assert_eq!(scene.get_camera().get_aspect_ratio(), 3.0);
     }
-    fn cast_ray(&self, ray: &Ray, depth: u32) -> Color {
-        let black_color = Color::new(SKY_COLOR[0], SKY_COLOR[1], SKY_COLOR[2]);
-        if depth >= MAX_RECURSION_DEPTH {
-            return black_color;
+
+    #[test]
+    fn test_area_light_soft_shadow() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 80.0, 1.0);
+        let mut scene = Scene::new(camera);
+        scene.add_object(Box::new(Sphere::new(
+            Vector::new(0.0, 1.0, 0.0),
+            0.5,
+            Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0),
+        )));
+        scene.add_light(Box::new(SphereLight::new(Vector::new(0.0, 5.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0),
+                                                  100.0, 64)));
+        let light = &scene.lights[0];
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let mut rng = Rng::new(1);
+
+        let lit = scene.direct_light(light.as_ref(), &Vector::new(10.0, 0.0, 0.0), &up, 1.0, 0.0, &mut rng).get()[0];
+        let umbra = scene.direct_light(light.as_ref(), &Vector::zero(), &up, 1.0, 0.0, &mut rng).get()[0];
+        let penumbra = scene.direct_light(light.as_ref(), &Vector::new(0.5, 0.0, 0.0), &up, 1.0, 0.0, &mut rng)
+            .get()[0];
+        assert!(lit > 0.0);
+        assert_eq!(umbra, 0.0);
+        let unblocked = light.get_intensity(&Vector::new(0.5, 0.0, 0.0))
+            * up.dot(&Vector::new(-0.5, 5.0, 0.0).normalize());
+        assert!(penumbra > 0.1 * unblocked && penumbra < 0.9 * unblocked);
+    }
+
+    #[test]
+    fn test_sample_count_keeps_brightness() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 80.0, 1.0);
+        let mut scene = Scene::new(camera);
+        for &samples in [16, 8, 15].iter() {
+            scene.add_light(Box::new(RectangleLight::new(Vector::new(0.0, 10.0, 0.0), Vector::new(1.0, 0.0, 0.0),
+                                                         Vector::new(0.0, 0.0, 1.0), Color::new(1.0, 1.0, 1.0),
+                                                         100.0, samples)));
         }
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let mut rng = Rng::new(1);
+        let lit: Vec<f64> = scene.lights.iter()
+            .map(|light| scene.direct_light(light.as_ref(), &Vector::zero(), &up, 1.0, 0.0, &mut rng).get()[0])
+            .collect();
+        assert!(lit[0] > 0.0);
+        // samples that do not make up a square grid light the point just as much
+        assert!((lit[1] / lit[0] - 1.0).abs() < 0.01, "{:?}", lit);
+        assert!((lit[2] / lit[0] - 1.0).abs() < 0.01, "{:?}", lit);
+    }
+
+    #[test]
+    fn test_glowing_sphere_lights_the_scene() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 80.0, 1.0);
+        let mut scene = Scene::new(camera);
+        let mut material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        material.set_emission(Color::new(1.0, 1.0, 1.0));
+        material.set_light_samples(4096);
+        scene.add_object(Box::new(Sphere::new(Vector::new(0.0, 5.0, 0.0), 1.0, material)));
+        scene.add_object(Box::new(Plane::new(
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::zero(),
+            Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0),
+        )));
+        assert_eq!(scene.get_lights().len(), 1);
+        assert!(scene.is_light_source(scene.objects[0].as_ref()));
+        assert!(!scene.is_light_source(scene.objects[1].as_ref()));
+
+        // a sphere of radiance 1 seen straight above covers (r / d)² of the light a white sky would
+        // deliver, the far half of it is hidden by the near half, and the light is white
+        let light = &scene.lights[0];
+        let intensity = scene.direct_light(light.as_ref(), &Vector::zero(), &Vector::new(0.0, 1.0, 0.0), 1.0,
+                                           0.0, &mut Rng::new(3)).get()[0];
+        assert!((intensity - 0.04).abs() < 0.004, "{}", intensity);
+    }
+
+    #[test]
+    fn test_moving_glowing_sphere() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0),
+                                 80.0, 1.0);
+        let mut scene = Scene::new(camera);
+        let mut material = Material::new_constant(Color::new(1.0, 1.0, 1.0), SurfaceType::Diffuse, 1.0, 1.0);
+        material.set_emission(Color::new(1.0, 1.0, 1.0));
+        material.set_light_samples(256);
+        let mut sphere = Sphere::new(Vector::new(0.0, 5.0, 0.0), 1.0, material);
+        sphere.set_end_center(Vector::new(20.0, 5.0, 0.0));
+        scene.add_object(Box::new(sphere));
+
+        // the light moves along with the sphere, and its own body does not shadow it
+        let light = &scene.lights[0];
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let start = scene.direct_light(light.as_ref(), &Vector::zero(), &up, 1.0, 0.0, &mut Rng::new(3)).get()[0];
+        let end = scene.direct_light(light.as_ref(), &Vector::new(20.0, 0.0, 0.0), &up, 1.0, 1.0, &mut Rng::new(3))
+            .get()[0];
+        assert!((end / start - 1.0).abs() < 0.01, "{} != {}", end, start);
+    }
+
+    #[test]
+    fn test_transparent_shadow() {
+        let mut scene = scene();
+        scene.add_object(Box::new(Sphere::new(
+            Vector::new(0.0, 10.0, 0.0),
+            1.0,
+            Material::new_constant(Color::new(1.0, 1.0, 1.0),
+                                   SurfaceType::Refractive { index: 1.5, transparency: 0.5 }, 1.0, 1.0),
+        )));
+        let up = Vector::new(0.0, 1.0, 0.0);
 
-        let intersection = self.trace(ray);
-        intersection.map(|i| self.get_color(ray, &i, depth))
-            .unwrap_or(black_color)
+        // through the glass sphere
+        assert_eq!(scene.shadow_transmission(Vector::zero(), &up, 20.0, 0.0), 0.25);
+        // into the opaque red sphere
+        let origin = Vector::new(0.0, 0.0, -10.0);
+        assert_eq!(scene.shadow_transmission(origin, &Vector::new(0.0, 0.0, 1.0), 20.0, 0.0), 0.0);
     }
 }
@@ -0,0 +1,1577 @@
+//! Declarative scene description in TOML.
+//!
+//! ```toml
+//! [image]
+//! width = 600
+//! height = 400
+//! samples = 16
+//! pattern = "jittered"
+//! filter = "mitchell"
+//! tone_mapping = "filmic"
+//! exposure = 0.5
+//!
+//! [integrator]
+//! type = "path"
+//! max_depth = 64
+//! roulette_depth = 3
+//!
+//! [camera]
+//! position = [0.0, 1.0, 5.0]
+//! look_at = [0.0, 0.0, -10.0]
+//! fov = 80.0
+//! aperture = 0.1
+//! blades = 6
+//! shutter_close = 1.0
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, 0.0, -10.0]
+//! radius = 2.0
+//! end_center = [0.5, 0.0, -10.0]
+//! material = { texture = { type = "constant", color = [1.0, 0.0, 0.0] } }
+//!
+//! [[objects]]
+//! type = "csg"
+//! operation = "difference"
+//! left = { type = "box", min = [-1.0, -1.0, -7.0], max = [1.0, 1.0, -5.0], material = { texture = { type = "constant", color = [0.8, 0.8, 0.8] } } }
+//! right = { type = "sphere", center = [0.0, 0.0, -6.0], radius = 1.3, material = { texture = { type = "constant", color = [0.8, 0.2, 0.2] } } }
+//!
+//! [[objects]]
+//! type = "mesh"
+//! path = "models/teapot.obj"
+//! transform = [{ scale = [0.5, 0.5, 0.5] }, { rotate_y = 45.0 }, { translate = [2.0, 0.0, -8.0] }]
+//!
+//! [[lights]]
+//! type = "directional"
+//! direction = [1.0, -1.0, -1.0]
+//! color = [1.0, 1.0, 1.0]
+//! intensity = 1.0
+//!
+//! [[lights]]
+//! type = "rectangle"
+//! center = [0.0, 5.0, -8.0]
+//! edge_u = [2.0, 0.0, 0.0]
+//! edge_v = [0.0, 0.0, 2.0]
+//! color = [1.0, 1.0, 1.0]
+//! intensity = 400.0
+//! samples = 16
+//!
+//! [[lights]]
+//! type = "spot"
+//! position = [0.0, 4.0, -6.0]
+//! direction = [0.0, -1.0, 0.0]
+//! inner = 20.0
+//! outer = 30.0
+//! color = [1.0, 1.0, 1.0]
+//! intensity = 800.0
+//! ```
+//!
+//! Colors are linear RGB triples, light colors may exceed 1.
+//! Relative paths inside the file are resolved against the directory of the scene file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use image::ImageError;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::base::{Color, Drawable};
+use crate::camera::{Camera, Projection};
+use crate::environment::sky::SkyEnvironment;
+use crate::environment::{Environment, GradientEnvironment, ImageEnvironment, SolidEnvironment};
+use crate::framebuffer::ToneMapping;
+use crate::integrator::path::PathTracer;
+use crate::integrator::whitted::WhittedIntegrator;
+use crate::integrator::Integrator;
+use crate::lighting::directional::DirectionalLight;
+use crate::lighting::disk::DiskLight;
+use crate::lighting::rectangle::RectangleLight;
+use crate::lighting::sphere::SphereLight;
+use crate::lighting::spherical::SphericalLight;
+use crate::lighting::spot::SpotLight;
+use crate::lighting::sun::SunLight;
+use crate::lighting::Lighting;
+use crate::material::image::{ImageTexture, Interpolation, WrapMode};
+use crate::material::microfacet::Microfacet;
+use crate::material::noise::{NoisePattern, NoiseTexture};
+use crate::material::{CheckeredPatternTexture, ConstantTexture, Material, SurfaceType, Texture};
+use crate::obj::{self, ObjError};
+use crate::objects::cone::Cone;
+use crate::objects::csg::{Csg, Operation};
+use crate::objects::cuboid::Cuboid;
+use crate::objects::cylinder::Cylinder;
+use crate::objects::disk::Disk;
+use crate::objects::group::Group;
+use crate::objects::instance::Instance;
+use crate::objects::plane::Plane;
+use crate::objects::sdf::{SdfNode, SdfObject};
+use crate::objects::sphere::Sphere;
+use crate::objects::torus::Torus;
+use crate::sampling::{Filter, SamplePattern};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum SceneError {
+    /// The scene file (or a file it references) could not be read.
+    Io(PathBuf, io::Error),
+    /// The document is not valid TOML or does not match the expected structure. `path` is the
+    /// location inside the document, e.g. `objects[2].material.surface`.
+    Parse { path: String, message: String },
+    /// A value has the right type but makes no sense, like a negative radius.
+    Invalid { path: String, message: String },
+    /// A mesh referenced by the scene could not be loaded.
+    Mesh { path: String, error: ObjError },
+    /// An image texture or environment map referenced by the scene could not be loaded.
+    Texture { path: String, error: ImageError },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(path, error) => write!(f, "cannot read {}: {}", path.display(), error),
+            SceneError::Parse { path, message } if path.is_empty() || path == "." => write!(f, "{}", message),
+            SceneError::Parse { path, message } => write!(f, "{}: {}", path, message),
+            SceneError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+            SceneError::Mesh { path, error } => write!(f, "{}: {}", path, error),
+            SceneError::Texture { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    image: ImageDescription,
+    camera: CameraDescription,
+    // objects and lights are dispatched on their `type` by hand, serde's internally tagged enums
+    // would lose the location of errors inside them
+    #[serde(default)]
+    objects: Vec<toml::Value>,
+    #[serde(default)]
+    lights: Vec<toml::Value>,
+    integrator: Option<toml::Value>,
+    environment: Option<toml::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDescription {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    threads: usize,
+    #[serde(default = "default_samples")]
+    samples: u32,
+    #[serde(default = "default_pattern")]
+    pattern: SamplePattern,
+    #[serde(default = "default_filter")]
+    filter: Filter,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default = "default_tone_mapping")]
+    tone_mapping: ToneMapping,
+    #[serde(default)]
+    exposure: f64,
+}
+
+fn default_samples() -> u32 {
+    1
+}
+
+fn default_pattern() -> SamplePattern {
+    SamplePattern::Regular
+}
+
+fn default_filter() -> Filter {
+    Filter::Box
+}
+
+fn default_tone_mapping() -> ToneMapping {
+    ToneMapping::Clamp
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    position: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_up")]
+    up: [f64; 3],
+    /// Vertical field of view in degrees, also for portrait images.
+    fov: f64,
+    #[serde(default = "default_projection")]
+    projection: Projection,
+    /// Radius of the lens, 0 for a pinhole camera.
+    #[serde(default)]
+    aperture: f64,
+    /// Distance to the plane in focus, by default the distance to `look_at`.
+    focal_distance: Option<f64>,
+    /// Corners of the lens opening, 0 for a round one.
+    #[serde(default)]
+    blades: u32,
+    /// Times the shutter opens and closes, moving objects start moving at time 0 and arrive at
+    /// time 1.
+    #[serde(default)]
+    shutter_open: f64,
+    #[serde(default)]
+    shutter_close: f64,
+}
+
+fn default_projection() -> Projection {
+    Projection::Perspective
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDescription {
+    center: [f64; 3],
+    radius: f64,
+    material: MaterialDescription,
+    /// Center at time 1 of a moving sphere.
+    end_center: Option<[f64; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaneDescription {
+    point: [f64; 3],
+    normal: [f64; 3],
+    material: MaterialDescription,
+    /// Point the plane passes through at time 1 when it moves.
+    end_point: Option<[f64; 3]>,
+}
+
+/// Axis-aligned box between two opposite corners.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BoxDescription {
+    min: [f64; 3],
+    max: [f64; 3],
+    material: MaterialDescription,
+}
+
+/// Upright cylinder or cone, `center` is the middle of its base.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UprightDescription {
+    center: [f64; 3],
+    radius: f64,
+    height: f64,
+    material: MaterialDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskDescription {
+    center: [f64; 3],
+    normal: [f64; 3],
+    radius: f64,
+    material: MaterialDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TorusDescription {
+    center: [f64; 3],
+    major_radius: f64,
+    minor_radius: f64,
+    material: MaterialDescription,
+}
+
+/// Boolean combination of two solids, given as object tables.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CsgDescription {
+    operation: Operation,
+    left: toml::Value,
+    right: toml::Value,
+}
+
+/// Object given by a tree of distance field `shape`s, traced with the given settings.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SdfObjectDescription {
+    shape: SdfDescription,
+    material: MaterialDescription,
+    #[serde(default = "default_epsilon")]
+    epsilon: f64,
+    #[serde(default = "default_max_steps")]
+    max_steps: u32,
+    #[serde(default = "default_one")]
+    step_scale: f64,
+}
+
+fn default_epsilon() -> f64 {
+    1e-4
+}
+
+fn default_max_steps() -> u32 {
+    256
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum SdfDescription {
+    Sphere { radius: f64 },
+    Box { half_size: [f64; 3] },
+    Torus { major_radius: f64, minor_radius: f64 },
+    Mandelbulb {
+        #[serde(default = "default_power")]
+        power: f64,
+        #[serde(default = "default_iterations")]
+        iterations: u32,
+    },
+    Translate { offset: [f64; 3], shape: Box<SdfDescription> },
+    Union { left: Box<SdfDescription>, right: Box<SdfDescription> },
+    SmoothUnion { left: Box<SdfDescription>, right: Box<SdfDescription>, smoothness: f64 },
+    Repeat { period: [f64; 3], shape: Box<SdfDescription> },
+    Twist { rate: f64, shape: Box<SdfDescription> },
+    Displace { amplitude: f64, frequency: f64, shape: Box<SdfDescription> },
+}
+
+fn default_power() -> f64 {
+    8.0
+}
+
+fn default_iterations() -> u32 {
+    8
+}
+
+/// Wavefront `.obj` file, using the materials of its `.mtl` libraries.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDescription {
+    path: String,
+}
+
+/// One step of an object transformation, a table with exactly one of the fields. Rotations are
+/// in degrees.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformStep {
+    translate: Option<[f64; 3]>,
+    scale: Option<[f64; 3]>,
+    rotate_x: Option<f64>,
+    rotate_y: Option<f64>,
+    rotate_z: Option<f64>,
+    rotate: Option<RotationDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RotationDescription {
+    axis: [f64; 3],
+    angle: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDescription {
+    time: f64,
+    transform: Vec<TransformStep>,
+}
+
+/// Meshes placed with a transformation, loaded once per file and shared by all their instances.
+type MeshCache = HashMap<PathBuf, Vec<Arc<dyn Drawable>>>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescription {
+    texture: TextureDescription,
+    #[serde(default)]
+    surface: SurfaceDescription,
+    #[serde(default = "default_one")]
+    albedo: f64,
+    #[serde(default = "default_one")]
+    glossiness: f64,
+    #[serde(default)]
+    emission: [f64; 3],
+    #[serde(default = "default_one")]
+    emission_strength: f64,
+    #[serde(default = "default_light_samples")]
+    light_samples: u32,
+    #[serde(default)]
+    specular: [f64; 3],
+    #[serde(default = "default_shininess")]
+    shininess: f64,
+}
+
+fn default_shininess() -> f64 {
+    32.0
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum TextureDescription {
+    Constant { color: [f64; 3] },
+    Checkered { color: [f64; 3], width: u32, height: u32 },
+    Image {
+        path: PathBuf,
+        #[serde(default = "default_interpolation")]
+        interpolation: Interpolation,
+        #[serde(default = "default_wrap")]
+        wrap: WrapMode,
+        #[serde(default = "default_one")]
+        scale: f64,
+    },
+    Noise {
+        pattern: NoisePattern,
+        colors: [[f64; 3]; 2],
+        #[serde(default = "default_one")]
+        scale: f64,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default = "default_true")]
+        solid: bool,
+    },
+}
+
+fn default_octaves() -> u32 {
+    6
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_interpolation() -> Interpolation {
+    Interpolation::Bilinear
+}
+
+fn default_wrap() -> WrapMode {
+    WrapMode::Repeat
+}
+
+#[derive(Deserialize, Default)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum SurfaceDescription {
+    #[default]
+    Diffuse,
+    Reflective { reflectivity: f64 },
+    Refractive { index: f64, transparency: f64 },
+    /// glTF style metallic-roughness surface using the texture as base colour.
+    Pbr {
+        #[serde(default)]
+        metallic: f64,
+        #[serde(default = "default_roughness")]
+        roughness: f64,
+        #[serde(default = "default_one")]
+        specular: f64,
+    },
+}
+
+fn default_roughness() -> f64 {
+    0.5
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirectionalLightDescription {
+    direction: [f64; 3],
+    color: [f64; 3],
+    intensity: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphericalLightDescription {
+    position: [f64; 3],
+    color: [f64; 3],
+    intensity: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RectangleLightDescription {
+    center: [f64; 3],
+    edge_u: [f64; 3],
+    edge_v: [f64; 3],
+    color: [f64; 3],
+    intensity: f64,
+    #[serde(default = "default_light_samples")]
+    samples: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskLightDescription {
+    center: [f64; 3],
+    normal: [f64; 3],
+    radius: f64,
+    color: [f64; 3],
+    intensity: f64,
+    #[serde(default = "default_light_samples")]
+    samples: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereLightDescription {
+    center: [f64; 3],
+    radius: f64,
+    color: [f64; 3],
+    intensity: f64,
+    #[serde(default = "default_light_samples")]
+    samples: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpotLightDescription {
+    position: [f64; 3],
+    direction: [f64; 3],
+    /// Degrees between the axis and the edge of the fully lit cone.
+    inner: f64,
+    /// Degrees between the axis and the edge of the light.
+    outer: f64,
+    color: [f64; 3],
+    intensity: f64,
+    gobo: Option<TextureDescription>,
+    /// Direction the top of the gobo faces, `+y` by default or `-z` for spots aimed straight up or
+    /// down.
+    up: Option<[f64; 3]>,
+}
+
+fn default_light_samples() -> u32 {
+    16
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolidEnvironmentDescription {
+    color: [f64; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GradientEnvironmentDescription {
+    bottom: [f64; 3],
+    top: [f64; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageEnvironmentDescription {
+    path: PathBuf,
+    #[serde(default = "default_one")]
+    strength: f64,
+    /// Degrees counterclockwise around the vertical axis.
+    #[serde(default)]
+    rotation: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkyEnvironmentDescription {
+    /// Degrees of the sun above the horizon.
+    elevation: f64,
+    /// Degrees clockwise from `-z` seen from above.
+    #[serde(default)]
+    azimuth: f64,
+    #[serde(default = "default_turbidity")]
+    turbidity: f64,
+    #[serde(default = "default_one")]
+    strength: f64,
+    #[serde(default = "default_true")]
+    sun: bool,
+    /// Angular diameter of the sun in degrees.
+    #[serde(default = "default_sun_size")]
+    sun_size: f64,
+    #[serde(default = "default_light_samples")]
+    sun_samples: u32,
+}
+
+fn default_turbidity() -> f64 {
+    3.0
+}
+
+fn default_sun_size() -> f64 {
+    0.53
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WhittedDescription {
+    #[serde(default = "default_whitted_depth")]
+    max_depth: u32,
+}
+
+fn default_whitted_depth() -> u32 {
+    5
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathTracerDescription {
+    #[serde(default = "default_path_depth")]
+    max_depth: u32,
+    #[serde(default = "default_roulette_depth")]
+    roulette_depth: u32,
+}
+
+fn default_path_depth() -> u32 {
+    64
+}
+
+fn default_roulette_depth() -> u32 {
+    3
+}
+
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| SceneError::Io(path.to_path_buf(), e))?;
+    parse_scene(&source, path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+/// Builds a scene from the TOML source, resolving referenced files relative to `base_dir`.
+pub fn parse_scene(source: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+    let mut deserializer = toml::Deserializer::new(source);
+    let description: SceneDescription = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|e| parse_error("", e))?;
+
+    let image = &description.image;
+    if image.width == 0 || image.height == 0 {
+        return Err(invalid("image", "width and height must be positive"));
+    }
+    if image.samples == 0 {
+        return Err(invalid("image.samples", "at least one sample per pixel is needed"));
+    }
+    let settings = RenderSettings {
+        width: image.width,
+        height: image.height,
+        threads: image.threads,
+        samples: image.samples,
+        pattern: image.pattern,
+        filter: image.filter,
+        seed: image.seed,
+        tone_mapping: image.tone_mapping,
+        exposure: image.exposure,
+        ..RenderSettings::default()
+    };
+
+    let mut scene = Scene::new(build_camera(&description.camera, settings.get_aspect_ratio())?);
+    scene.set_settings(settings);
+    let mut meshes = MeshCache::new();
+    for (i, object) in description.objects.into_iter().enumerate() {
+        add_object(&mut scene, object, &format!("objects[{}]", i), base_dir, &mut meshes)?;
+    }
+    for (i, light) in description.lights.into_iter().enumerate() {
+        scene.add_light(build_light(light, &format!("lights[{}]", i), base_dir)?);
+    }
+    if let Some(integrator) = description.integrator {
+        scene.set_integrator(build_integrator(integrator, "integrator")?);
+    }
+    if let Some(environment) = description.environment {
+        build_environment(&mut scene, environment, "environment", base_dir)?;
+    }
+    Ok(scene)
+}
+
+fn invalid(path: &str, message: &str) -> SceneError {
+    SceneError::Invalid { path: path.to_string(), message: message.to_string() }
+}
+
+fn parse_error(prefix: &str, error: serde_path_to_error::Error<toml::de::Error>) -> SceneError {
+    let inner = error.path().to_string();
+    let path = match (prefix.is_empty(), inner.as_str()) {
+        (true, _) => inner.clone(),
+        (false, ".") => prefix.to_string(),
+        (false, _) if inner.starts_with('[') => format!("{}{}", prefix, inner),
+        (false, _) => format!("{}.{}", prefix, inner),
+    };
+    SceneError::Parse { path: path, message: error.inner().to_string() }
+}
+
+fn deserialize<T: DeserializeOwned>(value: toml::Value, path: &str) -> Result<T, SceneError> {
+    serde_path_to_error::deserialize(value).map_err(|e| parse_error(path, e))
+}
+
+/// Removes the `type` key of a table describing an object or a light, returning its value.
+fn take_type(value: &mut toml::Value, path: &str) -> Result<String, SceneError> {
+    let table = value.as_table_mut().ok_or_else(|| invalid(path, "expected a table"))?;
+    match table.remove("type") {
+        Some(toml::Value::String(kind)) => Ok(kind),
+        Some(_) => Err(invalid(&format!("{}.type", path), "type must be a string")),
+        None => Err(invalid(path, "missing field `type`")),
+    }
+}
+
+fn unknown_type(path: &str, kind: &str, expected: &str) -> SceneError {
+    invalid(&format!("{}.type", path), &format!("unknown type `{}`, expected one of {}", kind, expected))
+}
+
+fn to_vector(v: &[f64; 3]) -> Vector {
+    Vector::from_array(*v)
+}
+
+fn to_color(c: &[f64; 3]) -> Color {
+    Color::from_array(*c)
+}
+
+fn to_direction(v: &[f64; 3], path: &str) -> Result<Vector, SceneError> {
+    let vector = to_vector(v);
+    if vector.euclidian_distance() == 0.0 {
+        return Err(invalid(path, "direction must not be zero"));
+    }
+    Ok(vector.normalize())
+}
+
+fn build_camera(camera: &CameraDescription, aspect_ratio: f64) -> Result<Camera, SceneError> {
+    match camera.projection {
+        Projection::Perspective | Projection::Orthographic => {
+            if camera.fov <= 0.0 || camera.fov >= 180.0 {
+                return Err(invalid("camera.fov", "field of view must be between 0 and 180 degrees"));
+            }
+        }
+        Projection::Equidistant | Projection::Equisolid => {
+            if camera.fov <= 0.0 || camera.fov > 360.0 {
+                return Err(invalid("camera.fov", "fisheye field of view must be between 0 and 360 degrees"));
+            }
+        }
+        // covers all directions whatever the field of view
+        Projection::Equirectangular => {}
+    }
+    let position = to_vector(&camera.position);
+    let look_at = to_vector(&camera.look_at);
+    let forward = look_at.minus(&position);
+    if forward.euclidian_distance() == 0.0 {
+        return Err(invalid("camera.look_at", "camera cannot look at its own position"));
+    }
+    let up = to_direction(&camera.up, "camera.up")?;
+    if forward.cross(&up).euclidian_distance() == 0.0 {
+        return Err(invalid("camera.up", "up vector must not be parallel to the viewing direction"));
+    }
+    if camera.aperture < 0.0 {
+        return Err(invalid("camera.aperture", "aperture must not be negative"));
+    }
+    if camera.blades == 1 || camera.blades == 2 {
+        return Err(invalid("camera.blades", "a lens opening needs at least three blades"));
+    }
+    let mut built = Camera::new(position, look_at, up, camera.fov, aspect_ratio);
+    built.set_projection(camera.projection);
+    built.set_aperture(camera.aperture);
+    built.set_blades(camera.blades);
+    if camera.shutter_close < camera.shutter_open {
+        return Err(invalid("camera.shutter_close", "the shutter cannot close before it opens"));
+    }
+    built.set_shutter(camera.shutter_open, camera.shutter_close);
+    if let Some(focal_distance) = camera.focal_distance {
+        if focal_distance <= 0.0 {
+            return Err(invalid("camera.focal_distance", "focal distance must be positive"));
+        }
+        built.set_focal_distance(focal_distance);
+    }
+    Ok(built)
+}
+
+fn add_object(scene: &mut Scene, mut object: toml::Value, path: &str, base_dir: &Path,
+              meshes: &mut MeshCache) -> Result<(), SceneError> {
+    let kind = take_type(&mut object, path)?;
+    let transform = take_transform(&mut object, path)?;
+    let keyframes = take_keyframes(&mut object, path)?;
+    // a fixed transformation is an animation with a single keyframe
+    let keyframes = match (transform, keyframes) {
+        (Some(_), Some(_)) => {
+            return Err(invalid(&format!("{}.keyframes", path), "expected either `transform` or `keyframes`"));
+        }
+        (Some(transform), None) => Some(vec![(0.0, transform)]),
+        (None, keyframes) => keyframes,
+    };
+    if kind == "mesh" {
+        return add_mesh(scene, object, keyframes, path, base_dir, meshes);
+    }
+    let shape = build_shape(&kind, object, path, base_dir)?;
+    let shape: Box<dyn Drawable> = match keyframes {
+        Some(keyframes) => Box::new(Instance::animated(Arc::from(shape), keyframes)),
+        None => shape,
+    };
+    // glowing objects light the scene from points picked on their surface
+    if shape.get_material().is_emissive() && shape.surface_area().is_none() {
+        return Err(invalid(&format!("{}.material.emission", path),
+                           "only spheres, disks, boxes, cylinders, cones and tori that are not stretched unevenly can glow"));
+    }
+    scene.add_object(shape);
+    Ok(())
+}
+
+fn add_mesh(scene: &mut Scene, object: toml::Value, keyframes: Option<Vec<(f64, Transform)>>, path: &str,
+            base_dir: &Path, meshes: &mut MeshCache) -> Result<(), SceneError> {
+    let mesh: MeshDescription = deserialize(object, path)?;
+    let mesh_path = base_dir.join(&mesh.path);
+    let load = || obj::load_obj(&mesh_path).map_err(|e| SceneError::Mesh {
+        path: format!("{}.path", path),
+        error: e,
+    });
+    match keyframes {
+        // loose triangles end up in the scene hierarchy, which traces fastest
+        None => {
+            for mesh in load()? {
+                scene.add_mesh(mesh);
+            }
+        }
+        Some(keyframes) => {
+            if !meshes.contains_key(&mesh_path) {
+                let loaded = load()?;
+                // glowing meshes light the scene, which needs their triangles where they are shown
+                if loaded.iter().any(|mesh| mesh.get_material().is_emissive()) {
+                    if keyframes.len() > 1 {
+                        return Err(invalid(&format!("{}.keyframes", path), "glowing meshes cannot be animated"));
+                    }
+                    for mesh in loaded {
+                        scene.add_mesh(mesh.transformed(&keyframes[0].1));
+                    }
+                    return Ok(());
+                }
+                let groups = loaded.into_iter()
+                    .map(|mesh| Arc::new(Group::from_mesh(mesh)) as Arc<dyn Drawable>)
+                    .collect();
+                meshes.insert(mesh_path.clone(), groups);
+            }
+            for group in meshes[&mesh_path].iter() {
+                scene.add_object(Box::new(Instance::animated(group.clone(), keyframes.clone())));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds an object of any type but `mesh` from its table without the `type` and `transform`.
+fn build_shape(kind: &str, object: toml::Value, path: &str, base_dir: &Path) -> Result<Box<dyn Drawable>, SceneError> {
+    Ok(match kind {
+        "sphere" => {
+            let sphere: SphereDescription = deserialize(object, path)?;
+            check_radius(sphere.radius, path)?;
+            let material = build_material(&sphere.material, &format!("{}.material", path), base_dir)?;
+            let mut built = Sphere::new(to_vector(&sphere.center), sphere.radius, material);
+            if let Some(end_center) = &sphere.end_center {
+                built.set_end_center(to_vector(end_center));
+            }
+            Box::new(built)
+        }
+        "plane" => {
+            let plane: PlaneDescription = deserialize(object, path)?;
+            let normal = to_direction(&plane.normal, &format!("{}.normal", path))?;
+            let material = build_material(&plane.material, &format!("{}.material", path), base_dir)?;
+            let mut built = Plane::new(normal, to_vector(&plane.point), material);
+            if let Some(end_point) = &plane.end_point {
+                built.set_end_point(to_vector(end_point));
+            }
+            Box::new(built)
+        }
+        "box" => {
+            let cuboid: BoxDescription = deserialize(object, path)?;
+            let material = build_material(&cuboid.material, &format!("{}.material", path), base_dir)?;
+            Box::new(Cuboid::new(to_vector(&cuboid.min), to_vector(&cuboid.max), material))
+        }
+        "cylinder" | "cone" => {
+            let upright: UprightDescription = deserialize(object, path)?;
+            check_radius(upright.radius, path)?;
+            if upright.height <= 0.0 {
+                return Err(invalid(&format!("{}.height", path), "height must be positive"));
+            }
+            let material = build_material(&upright.material, &format!("{}.material", path), base_dir)?;
+            let center = to_vector(&upright.center);
+            if kind == "cylinder" {
+                Box::new(Cylinder::new(center, upright.radius, upright.height, material))
+            } else {
+                Box::new(Cone::new(center, upright.radius, upright.height, material))
+            }
+        }
+        "disk" => {
+            let disk: DiskDescription = deserialize(object, path)?;
+            check_radius(disk.radius, path)?;
+            let normal = to_direction(&disk.normal, &format!("{}.normal", path))?;
+            let material = build_material(&disk.material, &format!("{}.material", path), base_dir)?;
+            Box::new(Disk::new(to_vector(&disk.center), normal, disk.radius, material))
+        }
+        "torus" => {
+            let torus: TorusDescription = deserialize(object, path)?;
+            if torus.minor_radius <= 0.0 || torus.major_radius < torus.minor_radius {
+                return Err(invalid(path, "radii must be positive with the minor radius not above the major one"));
+            }
+            let material = build_material(&torus.material, &format!("{}.material", path), base_dir)?;
+            Box::new(Torus::new(to_vector(&torus.center), torus.major_radius, torus.minor_radius, material))
+        }
+        "sdf" => {
+            let sdf: SdfObjectDescription = deserialize(object, path)?;
+            if sdf.epsilon <= 0.0 || sdf.max_steps == 0 {
+                return Err(invalid(path, "epsilon and max_steps must be positive"));
+            }
+            if sdf.step_scale <= 0.0 || sdf.step_scale > 1.0 {
+                return Err(invalid(&format!("{}.step_scale", path), "step scale must be in (0, 1]"));
+            }
+            let shape = build_sdf(sdf.shape, &format!("{}.shape", path))?;
+            let bounds = shape.bounding_box();
+            let material = build_material(&sdf.material, &format!("{}.material", path), base_dir)?;
+            let mut object = SdfObject::new(Box::new(shape), bounds, material);
+            object.set_epsilon(sdf.epsilon);
+            object.set_max_steps(sdf.max_steps);
+            object.set_step_scale(sdf.step_scale);
+            Box::new(object)
+        }
+        "csg" => {
+            let csg: CsgDescription = deserialize(object, path)?;
+            let left = build_solid(csg.left, &format!("{}.left", path), base_dir)?;
+            let right = build_solid(csg.right, &format!("{}.right", path), base_dir)?;
+            Box::new(Csg::new(csg.operation, left, right))
+        }
+        kind => return Err(unknown_type(path, kind, "`sphere`, `plane`, `box`, `cylinder`, `cone`, `disk`, `torus`, \
+                                                     `sdf`, `csg`, `mesh`")),
+    })
+}
+
+fn build_sdf(shape: SdfDescription, path: &str) -> Result<SdfNode, SceneError> {
+    let child = |shape: Box<SdfDescription>, name: &str| build_sdf(*shape, &format!("{}.{}", path, name)).map(Box::new);
+    Ok(match shape {
+        SdfDescription::Sphere { radius } => {
+            check_radius(radius, path)?;
+            SdfNode::Sphere { radius: radius }
+        }
+        SdfDescription::Box { half_size } => {
+            if half_size.iter().any(|&size| size <= 0.0) {
+                return Err(invalid(&format!("{}.half_size", path), "sizes must be positive"));
+            }
+            SdfNode::Box { half_size: to_vector(&half_size) }
+        }
+        SdfDescription::Torus { major_radius, minor_radius } => {
+            if minor_radius <= 0.0 || major_radius < minor_radius {
+                return Err(invalid(path, "radii must be positive with the minor radius not above the major one"));
+            }
+            SdfNode::Torus { major_radius: major_radius, minor_radius: minor_radius }
+        }
+        SdfDescription::Mandelbulb { power, iterations } => {
+            if power < 2.0 {
+                return Err(invalid(&format!("{}.power", path), "power must be at least 2"));
+            }
+            SdfNode::Mandelbulb { power: power, iterations: iterations }
+        }
+        SdfDescription::Translate { offset, shape } => {
+            SdfNode::Translate { offset: to_vector(&offset), node: child(shape, "shape")? }
+        }
+        SdfDescription::Union { left, right } => SdfNode::Union(child(left, "left")?, child(right, "right")?),
+        SdfDescription::SmoothUnion { left, right, smoothness } => {
+            if smoothness <= 0.0 {
+                return Err(invalid(&format!("{}.smoothness", path), "smoothness must be positive"));
+            }
+            SdfNode::SmoothUnion { left: child(left, "left")?, right: child(right, "right")?, smoothness: smoothness }
+        }
+        SdfDescription::Repeat { period, shape } => {
+            if period.iter().any(|&period| period < 0.0) {
+                return Err(invalid(&format!("{}.period", path), "periods must not be negative"));
+            }
+            SdfNode::Repeat { period: to_vector(&period), node: child(shape, "shape")? }
+        }
+        SdfDescription::Twist { rate, shape } => SdfNode::Twist { rate: rate, node: child(shape, "shape")? },
+        SdfDescription::Displace { amplitude, frequency, shape } => {
+            SdfNode::Displace { amplitude: amplitude, frequency: frequency, node: child(shape, "shape")? }
+        }
+    })
+}
+
+/// Builds a child of a CSG node, which has to enclose a volume.
+fn build_solid(mut object: toml::Value, path: &str, base_dir: &Path) -> Result<Box<dyn Drawable>, SceneError> {
+    let kind = take_type(&mut object, path)?;
+    let transform = take_transform(&mut object, path)?;
+    if kind == "mesh" || kind == "disk" || kind == "sdf" {
+        return Err(invalid(&format!("{}.type", path), &format!("a {} has no inside to combine", kind)));
+    }
+    let shape = build_shape(&kind, object, path, base_dir)?;
+    Ok(match transform {
+        Some(transform) => Box::new(Instance::new(Arc::from(shape), transform)),
+        None => shape,
+    })
+}
+
+/// Removes the optional `transform` list from an object table and combines its steps.
+fn take_transform(value: &mut toml::Value, path: &str) -> Result<Option<Transform>, SceneError> {
+    let steps = match value.as_table_mut().and_then(|table| table.remove("transform")) {
+        Some(steps) => steps,
+        None => return Ok(None),
+    };
+    let path = format!("{}.transform", path);
+    let steps: Vec<TransformStep> = deserialize(steps, &path)?;
+    Ok(Some(build_transform(&steps, &path)?))
+}
+
+/// Removes the optional `keyframes` list from an object table, transformations at points in time
+/// that an animated object moves between.
+fn take_keyframes(value: &mut toml::Value, path: &str) -> Result<Option<Vec<(f64, Transform)>>, SceneError> {
+    let keyframes = match value.as_table_mut().and_then(|table| table.remove("keyframes")) {
+        Some(keyframes) => keyframes,
+        None => return Ok(None),
+    };
+    let path = format!("{}.keyframes", path);
+    let keyframes: Vec<KeyframeDescription> = deserialize(keyframes, &path)?;
+    if keyframes.is_empty() {
+        return Err(invalid(&path, "at least one keyframe is needed"));
+    }
+    let mut built: Vec<(f64, Transform)> = Vec::with_capacity(keyframes.len());
+    for (i, keyframe) in keyframes.iter().enumerate() {
+        let keyframe_path = format!("{}[{}]", path, i);
+        if built.last().is_some_and(|&(time, _)| keyframe.time <= time) {
+            return Err(invalid(&format!("{}.time", keyframe_path), "keyframe times must increase"));
+        }
+        let transform = build_transform(&keyframe.transform, &format!("{}.transform", keyframe_path))?;
+        built.push((keyframe.time, transform));
+    }
+    Ok(Some(built))
+}
+
+/// Combines the steps of a `transform` list, errors are reported at `path[i]` for the step `i`.
+fn build_transform(steps: &[TransformStep], path: &str) -> Result<Transform, SceneError> {
+    let mut transform = Transform::identity();
+    for (i, step) in steps.iter().enumerate() {
+        let step_path = format!("{}[{}]", path, i);
+        let given = [step.translate.is_some(), step.scale.is_some(), step.rotate_x.is_some(),
+                     step.rotate_y.is_some(), step.rotate_z.is_some(), step.rotate.is_some()];
+        if given.iter().filter(|&&given| given).count() != 1 {
+            return Err(invalid(&step_path, "expected exactly one of `translate`, `scale`, `rotate_x`, \
+                                            `rotate_y`, `rotate_z`, `rotate`"));
+        }
+        let mut values: Vec<f64> = step.translate.iter().chain(&step.scale).flatten().copied().collect();
+        values.extend(step.rotate_x.iter().chain(&step.rotate_y).chain(&step.rotate_z));
+        if let Some(rotation) = &step.rotate {
+            values.extend(rotation.axis.iter().chain([&rotation.angle]));
+        }
+        if values.iter().any(|value| !value.is_finite()) {
+            return Err(invalid(&step_path, "transform values must be finite"));
+        }
+        transform = if let Some(offset) = &step.translate {
+            transform.translate(&to_vector(offset))
+        } else if let Some(factors) = &step.scale {
+            transform.scale(&to_vector(factors))
+                .ok_or_else(|| invalid(&step_path, "scale factors must not be zero"))?
+        } else if let Some(angle) = step.rotate_x {
+            transform.rotate_x(angle)
+        } else if let Some(angle) = step.rotate_y {
+            transform.rotate_y(angle)
+        } else if let Some(angle) = step.rotate_z {
+            transform.rotate_z(angle)
+        } else {
+            let rotation = step.rotate.as_ref().unwrap();
+            let axis = to_direction(&rotation.axis, &format!("{}.rotate.axis", step_path))?;
+            transform.rotate(&axis, rotation.angle)
+        };
+    }
+    Ok(transform)
+}
+
+fn build_texture(texture: &TextureDescription, path: &str, base_dir: &Path) -> Result<Box<dyn Texture>, SceneError> {
+    Ok(match texture {
+        TextureDescription::Constant { color } => Box::new(ConstantTexture::new(to_color(color))),
+        TextureDescription::Checkered { color, width, height } => {
+            if *width == 0 || *height == 0 {
+                return Err(invalid(path, "checker size must be positive"));
+            }
+            Box::new(CheckeredPatternTexture::new(to_color(color), *width, *height))
+        }
+        TextureDescription::Image { path: image_path, interpolation, wrap, scale } => {
+            if *scale <= 0.0 {
+                return Err(invalid(path, "scale must be positive"));
+            }
+            let mut texture = ImageTexture::open(base_dir.join(image_path), *interpolation, *wrap)
+                .map_err(|e| SceneError::Texture { path: format!("{}.path", path), error: e })?;
+            texture.set_scale(*scale);
+            Box::new(texture)
+        }
+        TextureDescription::Noise { pattern, colors, scale, seed, octaves, solid } => {
+            if *scale <= 0.0 {
+                return Err(invalid(path, "scale must be positive"));
+            }
+            let mut texture = NoiseTexture::new(*pattern, to_color(&colors[0]), to_color(&colors[1]), *scale, *seed);
+            texture.set_octaves(*octaves);
+            texture.set_solid(*solid);
+            Box::new(texture)
+        }
+    })
+}
+
+fn build_material(material: &MaterialDescription, path: &str, base_dir: &Path) -> Result<Material, SceneError> {
+    let texture = build_texture(&material.texture, &format!("{}.texture", path), base_dir)?;
+    let surface_path = format!("{}.surface", path);
+    let surface_type = match material.surface {
+        SurfaceDescription::Diffuse => SurfaceType::Diffuse,
+        SurfaceDescription::Reflective { reflectivity } => {
+            check_fraction(reflectivity, &surface_path, "reflectivity")?;
+            SurfaceType::Reflective { reflectivity: reflectivity }
+        }
+        SurfaceDescription::Refractive { index, transparency } => {
+            if index <= 0.0 {
+                return Err(invalid(&surface_path, "refraction index must be positive"));
+            }
+            check_fraction(transparency, &surface_path, "transparency")?;
+            SurfaceType::Refractive { index: index, transparency: transparency }
+        }
+        SurfaceDescription::Pbr { metallic, roughness, specular } => {
+            check_fraction(metallic, &surface_path, "metallic")?;
+            check_fraction(roughness, &surface_path, "roughness")?;
+            if specular < 0.0 {
+                return Err(invalid(&surface_path, "specular must not be negative"));
+            }
+            SurfaceType::Microfacet(Microfacet::new(metallic, roughness, specular))
+        }
+    };
+    let mut result = Material::new(texture, surface_type, material.albedo, material.glossiness);
+    if material.emission_strength < 0.0 {
+        return Err(invalid(&format!("{}.emission_strength", path), "emission strength must not be negative"));
+    }
+    result.set_emission(to_color(&material.emission).factor(material.emission_strength));
+    if material.light_samples == 0 {
+        return Err(invalid(&format!("{}.light_samples", path), "at least one shadow ray is needed"));
+    }
+    result.set_light_samples(material.light_samples);
+    if material.shininess < 0.0 {
+        return Err(invalid(&format!("{}.shininess", path), "shininess must not be negative"));
+    }
+    result.set_specular(to_color(&material.specular), material.shininess);
+    Ok(result)
+}
+
+fn check_fraction(value: f64, path: &str, name: &str) -> Result<(), SceneError> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(invalid(path, &format!("{} must be between 0 and 1", name)));
+    }
+    Ok(())
+}
+
+fn build_light(mut light: toml::Value, path: &str, base_dir: &Path) -> Result<Box<dyn Lighting>, SceneError> {
+    Ok(match take_type(&mut light, path)?.as_str() {
+        "directional" => {
+            let light: DirectionalLightDescription = deserialize(light, path)?;
+            to_direction(&light.direction, &format!("{}.direction", path))?;
+            Box::new(DirectionalLight::new(to_vector(&light.direction), to_color(&light.color), light.intensity))
+        }
+        "spherical" => {
+            let light: SphericalLightDescription = deserialize(light, path)?;
+            Box::new(SphericalLight::new(to_vector(&light.position), to_color(&light.color), light.intensity))
+        }
+        "rectangle" => {
+            let light: RectangleLightDescription = deserialize(light, path)?;
+            check_light_samples(light.samples, path)?;
+            let (edge_u, edge_v) = (to_vector(&light.edge_u), to_vector(&light.edge_v));
+            if edge_u.cross(&edge_v).euclidian_distance() == 0.0 {
+                return Err(invalid(&format!("{}.edge_v", path), "edges must not be parallel or zero"));
+            }
+            Box::new(RectangleLight::new(to_vector(&light.center), edge_u, edge_v, to_color(&light.color),
+                                         light.intensity, light.samples))
+        }
+        "disk" => {
+            let light: DiskLightDescription = deserialize(light, path)?;
+            check_light_samples(light.samples, path)?;
+            check_radius(light.radius, path)?;
+            let normal = to_direction(&light.normal, &format!("{}.normal", path))?;
+            Box::new(DiskLight::new(to_vector(&light.center), normal, light.radius, to_color(&light.color),
+                                    light.intensity, light.samples))
+        }
+        "sphere" => {
+            let light: SphereLightDescription = deserialize(light, path)?;
+            check_light_samples(light.samples, path)?;
+            check_radius(light.radius, path)?;
+            Box::new(SphereLight::new(to_vector(&light.center), light.radius, to_color(&light.color),
+                                      light.intensity, light.samples))
+        }
+        "spot" => {
+            let light: SpotLightDescription = deserialize(light, path)?;
+            let direction = to_direction(&light.direction, &format!("{}.direction", path))?;
+            if !(0.0..90.0).contains(&light.outer) || light.outer == 0.0 {
+                return Err(invalid(&format!("{}.outer", path), "cone angle must be between 0 and 90 degrees"));
+            }
+            if !(0.0..=light.outer).contains(&light.inner) {
+                return Err(invalid(&format!("{}.inner", path), "inner cone must not be wider than the outer one"));
+            }
+            let up = match &light.up {
+                Some(up) => to_direction(up, &format!("{}.up", path))?,
+                None if direction.cross(&Vector::new(0.0, 1.0, 0.0)).euclidian_distance() == 0.0 => {
+                    Vector::new(0.0, 0.0, -1.0)
+                }
+                None => Vector::new(0.0, 1.0, 0.0),
+            };
+            if direction.cross(&up).euclidian_distance() == 0.0 {
+                return Err(invalid(&format!("{}.up", path), "up vector must not be parallel to the direction"));
+            }
+            let mut spot = SpotLight::new(to_vector(&light.position), direction, &up, light.inner, light.outer,
+                                          to_color(&light.color), light.intensity);
+            if let Some(gobo) = &light.gobo {
+                spot.set_gobo(build_texture(gobo, &format!("{}.gobo", path), base_dir)?);
+            }
+            Box::new(spot)
+        }
+        kind => return Err(unknown_type(path, kind, "`directional`, `spherical`, `rectangle`, `disk`, `sphere`, `spot`")),
+    })
+}
+
+fn build_integrator(mut integrator: toml::Value, path: &str) -> Result<Box<dyn Integrator>, SceneError> {
+    Ok(match take_type(&mut integrator, path)?.as_str() {
+        "whitted" => {
+            let integrator: WhittedDescription = deserialize(integrator, path)?;
+            Box::new(WhittedIntegrator::new(integrator.max_depth))
+        }
+        "path" => {
+            let integrator: PathTracerDescription = deserialize(integrator, path)?;
+            Box::new(PathTracer::new(integrator.max_depth, integrator.roulette_depth))
+        }
+        kind => return Err(unknown_type(path, kind, "`whitted`, `path`")),
+    })
+}
+
+/// Sets the environment of the scene, a sky also adds its sun to the lights.
+fn build_environment(scene: &mut Scene, mut environment: toml::Value, path: &str, base_dir: &Path)
+                     -> Result<(), SceneError> {
+    let kind = take_type(&mut environment, path)?;
+    let light_samples = match environment.as_table_mut().and_then(|table| table.remove("light_samples")) {
+        Some(samples) => {
+            let samples_path = format!("{}.light_samples", path);
+            let samples: u32 = deserialize(samples, &samples_path)?;
+            if samples == 0 {
+                return Err(invalid(&samples_path, "at least one shadow ray is needed"));
+            }
+            Some(samples)
+        }
+        None => None,
+    };
+    // a sky comes with its sun, which has to be added after the environment replaced the old one
+    let mut sun = None;
+    let built: Arc<dyn Environment> = match kind.as_str() {
+        "solid" => {
+            let solid: SolidEnvironmentDescription = deserialize(environment, path)?;
+            Arc::new(SolidEnvironment::new(to_color(&solid.color)))
+        }
+        "gradient" => {
+            let gradient: GradientEnvironmentDescription = deserialize(environment, path)?;
+            Arc::new(GradientEnvironment::new(to_color(&gradient.bottom), to_color(&gradient.top)))
+        }
+        "image" => {
+            let image: ImageEnvironmentDescription = deserialize(environment, path)?;
+            if image.strength < 0.0 {
+                return Err(invalid(&format!("{}.strength", path), "strength must not be negative"));
+            }
+            let mut built = ImageEnvironment::load(base_dir.join(&image.path))
+                .map_err(|e| SceneError::Texture { path: format!("{}.path", path), error: e })?;
+            built.set_strength(image.strength);
+            built.set_rotation(image.rotation);
+            Arc::new(built)
+        }
+        "sky" => {
+            let sky: SkyEnvironmentDescription = deserialize(environment, path)?;
+            if !(1.0..=10.0).contains(&sky.turbidity) {
+                return Err(invalid(&format!("{}.turbidity", path), "turbidity must be between 1 and 10"));
+            }
+            if sky.strength < 0.0 {
+                return Err(invalid(&format!("{}.strength", path), "strength must not be negative"));
+            }
+            if !(0.0..180.0).contains(&sky.sun_size) {
+                return Err(invalid(&format!("{}.sun_size", path), "sun_size must be between 0 and 180 degrees"));
+            }
+            if sky.sun_samples == 0 {
+                return Err(invalid(&format!("{}.sun_samples", path), "at least one shadow ray is needed"));
+            }
+            let mut built = SkyEnvironment::new(sky.elevation, sky.azimuth, sky.turbidity);
+            built.set_strength(sky.strength);
+            if sky.sun && sky.elevation > 0.0 {
+                sun = Some(SunLight::new(built.get_sun_direction().clone(), sky.sun_size, built.get_sun_color(),
+                                         built.get_sun_intensity(), sky.sun_samples));
+            }
+            Arc::new(built)
+        }
+        kind => return Err(unknown_type(path, kind, "`solid`, `gradient`, `image`, `sky`")),
+    };
+    scene.set_environment(built, light_samples);
+    if let Some(sun) = sun {
+        scene.add_environment_light(Box::new(sun));
+    }
+    Ok(())
+}
+
+fn check_light_samples(samples: u32, path: &str) -> Result<(), SceneError> {
+    if samples == 0 {
+        return Err(invalid(&format!("{}.samples", path), "at least one shadow ray is needed"));
+    }
+    Ok(())
+}
+
+fn check_radius(radius: f64, path: &str) -> Result<(), SceneError> {
+    if radius <= 0.0 {
+        return Err(invalid(&format!("{}.radius", path), "radius must be positive"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Ray;
+
+    const SCENE: &str = r#"
+[image]
+width = 40
+height = 20
+
+[camera]
+position = [0.0, 0.0, 0.0]
+look_at = [0.0, 0.0, -1.0]
+fov = 90.0
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -5.0]
+radius = 1.0
+material = { texture = { type = "constant", color = [1.0, 0.0, 0.0] }, surface = { type = "reflective", reflectivity = 0.5 } }
+
+[[lights]]
+type = "spherical"
+position = [0.0, 5.0, 0.0]
+color = [1.0, 1.0, 1.0]
+intensity = 10.0
+"#;
+
+    fn error_message(source: &str) -> String {
+        match parse_scene(source, Path::new("")) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_scene() {
+        let scene = parse_scene(SCENE, Path::new("")).unwrap();
+        assert_eq!(scene.get_settings().width, 40);
+        assert_eq!(scene.get_camera().get_aspect_ratio(), 2.0);
+        assert_eq!(scene.get_settings().tone_mapping, ToneMapping::Clamp);
+
+        let filmic = SCENE.replace("height = 20", "height = 20\ntone_mapping = \"filmic\"\nexposure = -1.5");
+        let scene = parse_scene(&filmic, Path::new("")).unwrap();
+        assert_eq!(scene.get_settings().tone_mapping, ToneMapping::Filmic);
+        assert_eq!(scene.get_settings().exposure, -1.5);
+
+        // the camera looks at a point one unit away unless focused elsewhere
+        assert_eq!(scene.get_camera().get_focal_distance(), 1.0);
+        let lens = SCENE.replace("fov = 90.0", "fov = 90.0\naperture = 0.1\nfocal_distance = 5.0\nblades = 6");
+        let scene = parse_scene(&lens, Path::new("")).unwrap();
+        assert_eq!(scene.get_camera().get_aperture(), 0.1);
+        assert_eq!(scene.get_camera().get_focal_distance(), 5.0);
+        assert_eq!(scene.get_camera().get_blades(), 6);
+
+        let fisheye = SCENE.replace("fov = 90.0", "fov = 200.0\nprojection = \"equisolid\"");
+        let scene = parse_scene(&fisheye, Path::new("")).unwrap();
+        assert_eq!(scene.get_camera().get_projection(), Projection::Equisolid);
+        let wide = fisheye.replace("equisolid", "perspective");
+        assert_eq!(error_message(&wide), "camera.fov: field of view must be between 0 and 180 degrees");
+    }
+
+    #[test]
+    fn test_environment() {
+        let gradient = format!("{}\n[environment]\ntype = \"gradient\"\nbottom = [0.0, 0.0, 0.0]\ntop = [0.0, 0.0, 2.0]\n\
+                                light_samples = 8\n", SCENE);
+        let scene = parse_scene(&gradient, Path::new("")).unwrap();
+        assert_eq!(scene.get_background(&Vector::new(1.0, 0.0, 0.0)), Color::new(0.0, 0.0, 1.0));
+        assert!(scene.is_environment_sampled());
+        assert_eq!(scene.get_lights().len(), 2);
+
+        let unlit = gradient.replace("light_samples = 8", "");
+        assert!(!parse_scene(&unlit, Path::new("")).unwrap().is_environment_sampled());
+
+        let no_shadow_rays = gradient.replace("light_samples = 8", "light_samples = 0");
+        assert_eq!(error_message(&no_shadow_rays), "environment.light_samples: at least one shadow ray is needed");
+        let missing = format!("{}\n[environment]\ntype = \"image\"\npath = \"missing.hdr\"\n", SCENE);
+        assert!(error_message(&missing).starts_with("environment.path: "));
+    }
+
+    #[test]
+    fn test_sky() {
+        let sky = format!("{}\n[environment]\ntype = \"sky\"\nelevation = 30.0\nazimuth = 90.0\n", SCENE);
+        let mut scene = parse_scene(&sky, Path::new("")).unwrap();
+        // the sun is added to the spherical light
+        assert_eq!(scene.get_lights().len(), 2);
+        let towards_sun = Vector::new(1.0, 0.6, 0.0);
+        let away = Vector::new(-1.0, 0.6, 0.0);
+        assert!(scene.get_background(&towards_sun).luminance() > scene.get_background(&away).luminance());
+        // and goes down with the sky, like when the environment is replaced on the command line
+        scene.set_environment(Arc::new(SolidEnvironment::new(Color::new(1.0, 1.0, 1.0))), Some(4));
+        assert_eq!(scene.get_lights().len(), 2);
+        scene.set_environment(Arc::new(SolidEnvironment::new(Color::new(1.0, 1.0, 1.0))), None);
+        assert_eq!(scene.get_lights().len(), 1);
+
+        let no_sun = sky.replace("azimuth = 90.0", "sun = false");
+        assert_eq!(parse_scene(&no_sun, Path::new("")).unwrap().get_lights().len(), 1);
+        let hazy = sky.replace("azimuth = 90.0", "turbidity = 20.0");
+        assert_eq!(error_message(&hazy), "environment.turbidity: turbidity must be between 1 and 10");
+    }
+
+    #[test]
+    fn test_spot_light() {
+        let spot = SCENE.replace("type = \"spherical\"\nposition = [0.0, 5.0, 0.0]",
+                                 "type = \"spot\"\nposition = [0.0, 5.0, 0.0]\ndirection = [0.0, -1.0, 0.0]\n\
+                                  inner = 20.0\nouter = 30.0\ngobo = { type = \"checkered\", color = [1.0, 0.0, 0.0], \
+                                  width = 1, height = 1 }");
+        let scene = parse_scene(&spot, Path::new("")).unwrap();
+        assert!(scene.get_lights()[0].get_intensity(&Vector::zero()) > 0.0);
+        assert_eq!(scene.get_lights()[0].get_intensity(&Vector::new(5.0, 0.0, 0.0)), 0.0);
+
+        let wide = spot.replace("inner = 20.0", "inner = 40.0");
+        assert_eq!(error_message(&wide), "lights[0].inner: inner cone must not be wider than the outer one");
+        let parallel = spot.replace("inner = 20.0", "inner = 20.0\nup = [0.0, 2.0, 0.0]");
+        assert_eq!(error_message(&parallel), "lights[0].up: up vector must not be parallel to the direction");
+        let flat_checker = spot.replace("width = 1", "width = 0");
+        assert_eq!(error_message(&flat_checker), "lights[0].gobo: checker size must be positive");
+    }
+
+    #[test]
+    fn test_primitives() {
+        let material = "material = { texture = { type = \"constant\", color = [1.0, 1.0, 1.0] } }";
+        let primitives = [
+            "type = \"box\"\nmin = [-1.0, -1.0, -6.0]\nmax = [1.0, 1.0, -4.0]",
+            "type = \"cylinder\"\ncenter = [0.0, -1.0, -5.0]\nradius = 1.0\nheight = 2.0",
+            "type = \"cone\"\ncenter = [0.0, -1.0, -5.0]\nradius = 1.0\nheight = 2.0",
+            "type = \"disk\"\ncenter = [0.0, 0.0, -5.0]\nnormal = [0.0, 0.0, 1.0]\nradius = 1.0",
+            "type = \"torus\"\ncenter = [0.0, 0.0, 0.0]\nmajor_radius = 1.0\nminor_radius = 0.5\ntransform = [{ translate = [0.0, 0.0, -5.0] }]",
+        ];
+        // only the camera and the image settings of the test scene
+        let header = &SCENE[..SCENE.find("[[objects]]").unwrap()];
+        for primitive in primitives.iter() {
+            let source = format!("{}[[objects]]\n{}\n{}\n", header, primitive, material);
+            let scene = parse_scene(&source, Path::new("")).unwrap();
+            let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+            assert!(scene.trace(&ray).is_some(), "{}", primitive);
+        }
+    }
+
+    #[test]
+    fn test_csg() {
+        let header = &SCENE[..SCENE.find("[[objects]]").unwrap()];
+        let source = format!(r#"{}
+[[objects]]
+type = "csg"
+operation = "difference"
+left = {{ type = "box", min = [-1.0, -1.0, -6.0], max = [1.0, 1.0, -4.0], {material} }}
+right = {{ type = "sphere", center = [0.0, 0.0, 0.0], radius = 0.5, transform = [{{ translate = [0.0, 0.0, -4.0] }}], {material} }}
+"#, header, material = "material = { texture = { type = \"constant\", color = [1.0, 1.0, 1.0] } }");
+        let scene = parse_scene(&source, Path::new("")).unwrap();
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.trace(&ray).unwrap().get_distance(), 4.5);
+
+        let mesh = source.replace("type = \"box\"", "type = \"mesh\", path = \"missing.obj\"");
+        assert_eq!(error_message(&mesh), "objects[0].left.type: a mesh has no inside to combine");
+        let radius = source.replace("radius = 0.5", "radius = 0.0");
+        assert_eq!(error_message(&radius), "objects[0].right.radius: radius must be positive");
+    }
+
+    #[test]
+    fn test_sdf() {
+        let header = &SCENE[..SCENE.find("[[objects]]").unwrap()];
+        let source = format!(r#"{}
+[[objects]]
+type = "sdf"
+material = {{ texture = {{ type = "constant", color = [1.0, 1.0, 1.0] }} }}
+shape = {{ type = "translate", offset = [0.0, 0.0, -5.0], shape = {{ type = "smooth_union", smoothness = 0.2, left = {{ type = "sphere", radius = 1.0 }}, right = {{ type = "box", half_size = [2.0, 0.1, 0.1] }} }} }}
+"#, header);
+        let scene = parse_scene(&source, Path::new("")).unwrap();
+        let ray = Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        assert!((scene.trace(&ray).unwrap().get_distance() - 4.0).abs() < 1e-3);
+
+        let flat = source.replace("half_size = [2.0, 0.1, 0.1]", "half_size = [2.0, 0.0, 0.1]");
+        assert_eq!(error_message(&flat), "objects[0].shape.shape.right.half_size: sizes must be positive");
+    }
+
+    #[test]
+    fn test_motion_blur() {
+        let shutter = SCENE.replace("fov = 90.0", "fov = 90.0\nshutter_open = 0.0\nshutter_close = 1.0");
+        let moving = shutter.replace("radius = 1.0", "radius = 1.0\nend_center = [3.0, 0.0, -5.0]");
+        let scene = parse_scene(&moving, Path::new("")).unwrap();
+        assert!(scene.get_camera().has_motion_blur());
+        let ray = || Ray::from(Vector::zero(), Vector::new(0.0, 0.0, -1.0));
+        assert!(scene.trace(&ray()).is_some());
+        assert!(scene.trace(&ray().with_time(1.0)).is_none());
+
+        let keyframes = shutter.replace("radius = 1.0", "radius = 1.0\nkeyframes = [{ time = 0.0, transform = [] }, \
+                                                          { time = 1.0, transform = [{ translate = [3.0, 0.0, 0.0] }] }]");
+        let scene = parse_scene(&keyframes, Path::new("")).unwrap();
+        assert!(scene.trace(&ray()).is_some());
+        assert!(scene.trace(&ray().with_time(1.0)).is_none());
+
+        let backwards = keyframes.replace("time = 1.0", "time = 0.0");
+        assert_eq!(error_message(&backwards), "objects[0].keyframes[1].time: keyframe times must increase");
+        let both = keyframes.replace("radius = 1.0", "radius = 1.0\ntransform = []");
+        assert_eq!(error_message(&both), "objects[0].keyframes: expected either `transform` or `keyframes`");
+        let reversed = shutter.replace("shutter_open = 0.0", "shutter_open = 2.0");
+        assert_eq!(error_message(&reversed), "camera.shutter_close: the shutter cannot close before it opens");
+    }
+
+    #[test]
+    fn test_glowing_objects() {
+        let glowing = SCENE.replace("surface = { type = \"reflective\", reflectivity = 0.5 }",
+                                    "surface = { type = \"diffuse\" }, emission = [1.0, 1.0, 1.0]");
+        assert_eq!(parse_scene(&glowing, Path::new("")).unwrap().get_lights().len(), 2);
+        let scaled = glowing.replace("radius = 1.0", "radius = 1.0\ntransform = [{ scale = [2.0, 2.0, 2.0] }]");
+        assert_eq!(parse_scene(&scaled, Path::new("")).unwrap().get_lights().len(), 2);
+        let torus = glowing.replace("\"sphere\"", "\"torus\"")
+            .replace("radius = 1.0", "major_radius = 1.0\nminor_radius = 0.5");
+        assert_eq!(parse_scene(&torus, Path::new("")).unwrap().get_lights().len(), 2);
+
+        let stretched = glowing.replace("radius = 1.0", "radius = 1.0\ntransform = [{ scale = [1.0, 2.0, 1.0] }]");
+        assert!(error_message(&stretched).starts_with("objects[0].material.emission: only spheres, disks, boxes"));
+        let plane = glowing.replace("\"sphere\"", "\"plane\"").replace("center", "point")
+            .replace("radius = 1.0", "normal = [0.0, 0.0, 1.0]");
+        assert!(error_message(&plane).starts_with("objects[0].material.emission: "));
+    }
+
+    #[test]
+    fn test_error_paths() {
+        let wrong_type = SCENE.replace("radius = 1.0", "radius = \"big\"");
+        assert!(error_message(&wrong_type).starts_with("objects[0].radius: "));
+
+        let unknown_surface = SCENE.replace("\"reflective\"", "\"shiny\"");
+        assert!(error_message(&unknown_surface).starts_with("objects[0].material.surface.type: unknown variant `shiny`"));
+
+        let unknown_object = SCENE.replace("\"sphere\"", "\"blob\"");
+        assert!(error_message(&unknown_object).starts_with("objects[0].type: unknown type `blob`"));
+
+        let negative_radius = SCENE.replace("radius = 1.0", "radius = -1.0");
+        assert_eq!(error_message(&negative_radius), "objects[0].radius: radius must be positive");
+
+        let no_shadow_rays = SCENE.replace("type = \"spherical\"\nposition = [0.0, 5.0, 0.0]",
+                                           "type = \"sphere\"\ncenter = [0.0, 5.0, 0.0]\nradius = 1.0\nsamples = 0");
+        assert_eq!(error_message(&no_shadow_rays), "lights[0].samples: at least one shadow ray is needed");
+
+        let missing_texture = SCENE.replace("type = \"constant\", color = [1.0, 0.0, 0.0]",
+                                            "type = \"image\", path = \"missing.png\"");
+        assert!(error_message(&missing_texture).starts_with("objects[0].material.texture.path: "));
+
+        let flat = SCENE.replace("radius = 1.0", "radius = 1.0\ntransform = [{ rotate_y = 45.0 }, { scale = [1.0, 0.0, 1.0] }]");
+        assert_eq!(error_message(&flat), "objects[0].transform[1]: scale factors must not be zero");
+        let broken = SCENE.replace("radius = 1.0", "radius = 1.0\ntransform = [{ scale = [nan, 1.0, 1.0] }]");
+        assert_eq!(error_message(&broken), "objects[0].transform[0]: transform values must be finite");
+
+        let flat_cone = SCENE.replace("center = [0.0, 0.0, -5.0]", "center = [0.0, 0.0, -5.0]\nheight = 0.0")
+            .replace("\"sphere\"", "\"cone\"");
+        assert_eq!(error_message(&flat_cone), "objects[0].height: height must be positive");
+
+        let rough = SCENE.replace("type = \"reflective\", reflectivity = 0.5", "type = \"pbr\", metallic = 1.0, roughness = 1.5");
+        assert_eq!(error_message(&rough), "objects[0].material.surface: roughness must be between 0 and 1");
+
+        let blades = SCENE.replace("fov = 90.0", "fov = 90.0\naperture = 0.1\nblades = 2");
+        assert_eq!(error_message(&blades), "camera.blades: a lens opening needs at least three blades");
+
+        let two_steps = SCENE.replace("radius = 1.0", "radius = 1.0\ntransform = [{ rotate_x = 1.0, rotate_y = 2.0 }]");
+        assert!(error_message(&two_steps).starts_with("objects[0].transform[0]: expected exactly one of"));
+    }
+}
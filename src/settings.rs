@@ -0,0 +1,51 @@
+use crate::framebuffer::ToneMapping;
+use crate::sampling::{Filter, SamplePattern};
+
+/// Options controlling how `Scene::render` produces an image, independent of the scene content.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    /// Number of worker threads, 0 uses every available core.
+    pub threads: usize,
+    /// Width and height of the square tiles the image is split into for the workers.
+    pub tile_size: u32,
+    /// Number of rays per pixel.
+    pub samples: u32,
+    pub pattern: SamplePattern,
+    pub filter: Filter,
+    /// Seed for everything random in the render. The same seed gives the same image.
+    pub seed: u64,
+    pub tone_mapping: ToneMapping,
+    /// Exposure adjustment in stops applied before tone mapping.
+    pub exposure: f64,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            width: 800,
+            height: 600,
+            threads: 0,
+            tile_size: 32,
+            samples: 1,
+            pattern: SamplePattern::Regular,
+            filter: Filter::Box,
+            seed: 0,
+            tone_mapping: ToneMapping::Clamp,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+    pub fn get_thread_count(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    }
+}
@@ -0,0 +1,498 @@
+//! Affine transformations of points, directions and normals.
+
+use std::ops;
+
+use crate::base::{BoundingBox, Ray};
+use crate::vector::Vector;
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { m: m }
+    }
+    pub fn identity() -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn translation(offset: &Vector) -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, offset.get_x()],
+            [0.0, 1.0, 0.0, offset.get_y()],
+            [0.0, 0.0, 1.0, offset.get_z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn scaling(factors: &Vector) -> Matrix4 {
+        Matrix4::new([
+            [factors.get_x(), 0.0, 0.0, 0.0],
+            [0.0, factors.get_y(), 0.0, 0.0],
+            [0.0, 0.0, factors.get_z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    /// Counter-clockwise rotation by `degrees` around `axis` when looking against the axis.
+    pub fn rotation(axis: &Vector, degrees: f64) -> Matrix4 {
+        let axis = axis.normalize();
+        let (x, y, z) = (axis.get_x(), axis.get_y(), axis.get_z());
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Matrix4::new([
+            [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y, 0.0],
+            [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x, 0.0],
+            [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.m[row][column]
+    }
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Matrix4::new(m)
+    }
+    /// Inverse by Gauss-Jordan elimination, `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m;
+        let mut inverse = Matrix4::identity().m;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+        Some(Matrix4::new(inverse))
+    }
+    pub fn transform_point(&self, point: &Vector) -> Vector {
+        let m = &self.m;
+        let (x, y, z) = (point.get_x(), point.get_y(), point.get_z());
+        Vector::new(m[0][0] * x + m[0][1] * y + m[0][2] * z + m[0][3],
+                    m[1][0] * x + m[1][1] * y + m[1][2] * z + m[1][3],
+                    m[2][0] * x + m[2][1] * y + m[2][2] * z + m[2][3])
+    }
+    /// Transforms a direction, ignoring the translation.
+    pub fn transform_vector(&self, vector: &Vector) -> Vector {
+        let m = &self.m;
+        let (x, y, z) = (vector.get_x(), vector.get_y(), vector.get_z());
+        Vector::new(m[0][0] * x + m[0][1] * y + m[0][2] * z,
+                    m[1][0] * x + m[1][1] * y + m[1][2] * z,
+                    m[2][0] * x + m[2][1] * y + m[2][2] * z)
+    }
+}
+
+impl ops::Mul<&Matrix4> for &Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: &Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4::new(m)
+    }
+}
+
+/// Invertible affine transformation from object space to world space, kept together with its
+/// inverse. Transformations are chained in the order they are applied:
+/// `Transform::identity().scale(..).rotate_y(..).translate(..)` scales first.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    /// Returns `None` if the matrix cannot be inverted, e.g. because it scales by zero.
+    pub fn new(matrix: Matrix4) -> Option<Transform> {
+        let inverse = matrix.inverse()?;
+        Some(Transform {
+            matrix: matrix,
+            inverse: inverse,
+        })
+    }
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+    pub fn translate(&self, offset: &Vector) -> Transform {
+        self.then(&Transform {
+            matrix: Matrix4::translation(offset),
+            inverse: Matrix4::translation(&offset.neg()),
+        })
+    }
+    /// Scales along the axes, `None` if a factor is zero.
+    pub fn scale(&self, factors: &Vector) -> Option<Transform> {
+        Some(self.then(&Transform::new(Matrix4::scaling(factors))?))
+    }
+    /// Rotates by `degrees` around `axis` through the origin.
+    pub fn rotate(&self, axis: &Vector, degrees: f64) -> Transform {
+        let rotation = Matrix4::rotation(axis, degrees);
+        self.then(&Transform {
+            inverse: rotation.transpose(),
+            matrix: rotation,
+        })
+    }
+    pub fn rotate_x(&self, degrees: f64) -> Transform {
+        self.rotate(&Vector::new(1.0, 0.0, 0.0), degrees)
+    }
+    pub fn rotate_y(&self, degrees: f64) -> Transform {
+        self.rotate(&Vector::new(0.0, 1.0, 0.0), degrees)
+    }
+    pub fn rotate_z(&self, degrees: f64) -> Transform {
+        self.rotate(&Vector::new(0.0, 0.0, 1.0), degrees)
+    }
+    /// Applies this transformation first and `other` after it.
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            matrix: &other.matrix * &self.matrix,
+            inverse: &self.inverse * &other.inverse,
+        }
+    }
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse.clone(),
+            inverse: self.matrix.clone(),
+        }
+    }
+    /// Transformation part of the way to `other`, see `Decomposition::interpolate`.
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Option<Transform> {
+        self.decompose().interpolate(&other.decompose(), t)
+    }
+    pub fn decompose(&self) -> Decomposition {
+        Decomposition::new(&self.matrix)
+    }
+    pub fn get_matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+    /// Factor by which the transformation scales all lengths, `None` if it stretches some
+    /// directions more than others.
+    pub fn uniform_scale(&self) -> Option<f64> {
+        // the columns of the linear part are where the unit axes end up
+        let axes = [0, 1, 2].map(|j| Vector::new(self.matrix.get(0, j), self.matrix.get(1, j), self.matrix.get(2, j)));
+        let scale = axes[0].euclidian_distance();
+        let tolerance = 1e-9 * scale * scale;
+        let similar = (0..3).all(|i| {
+            (axes[i].dot(&axes[i]) - scale * scale).abs() < tolerance
+                && axes[i].dot(&axes[(i + 1) % 3]).abs() < tolerance
+        });
+        if similar { Some(scale) } else { None }
+    }
+    pub fn apply_point(&self, point: &Vector) -> Vector {
+        self.matrix.transform_point(point)
+    }
+    pub fn apply_vector(&self, vector: &Vector) -> Vector {
+        self.matrix.transform_vector(vector)
+    }
+    /// Normals stay perpendicular to surfaces by transforming with the inverse transpose. The
+    /// result is not normalized.
+    pub fn apply_normal(&self, normal: &Vector) -> Vector {
+        self.inverse.transpose().transform_vector(normal)
+    }
+    pub fn invert_point(&self, point: &Vector) -> Vector {
+        self.inverse.transform_point(point)
+    }
+    /// Moves the ray into the space the transformation starts from. The direction of the result
+    /// is normalized, distances along it have to be divided by the returned factor to get
+    /// distances along the original ray.
+    pub fn invert_ray(&self, ray: &Ray) -> (Ray, f64) {
+        let direction = self.inverse.transform_vector(ray.get_direction());
+        let length = direction.euclidian_distance();
+        let local = Ray::from(self.invert_point(ray.get_origin()), direction.factor(1.0 / length));
+        (local.with_time(ray.get_time()), length)
+    }
+    /// Box around the transformed corners of `bounds`. Infinite boxes stay infinite.
+    pub fn apply_bounds(&self, bounds: &BoundingBox) -> BoundingBox {
+        if !bounds.is_finite() {
+            return BoundingBox::infinite();
+        }
+        let (min, max) = (bounds.get_min(), bounds.get_max());
+        (0..8).fold(BoundingBox::empty(), |result, corner| {
+            let pick = |axis: usize| if corner & (1 << axis) == 0 { min.get_axis(axis) } else { max.get_axis(axis) };
+            result.include(&self.apply_point(&Vector::new(pick(0), pick(1), pick(2))))
+        })
+    }
+}
+
+/// Rotation as a unit quaternion `w + x i + y j + z k`.
+#[derive(PartialEq, Debug, Clone)]
+struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Quaternion {
+    fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion {
+            w: w,
+            x: x,
+            y: y,
+            z: z,
+        }
+    }
+    /// Quaternion of a rotation matrix given by its rows.
+    fn from_rows(r: &[Vector; 3]) -> Quaternion {
+        let m = |i: usize, j: usize| r[i].get_axis(j);
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        // divides by the largest of the components to stay accurate
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new(s / 4.0, (m(2, 1) - m(1, 2)) / s, (m(0, 2) - m(2, 0)) / s, (m(1, 0) - m(0, 1)) / s)
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+            Quaternion::new((m(2, 1) - m(1, 2)) / s, s / 4.0, (m(0, 1) + m(1, 0)) / s, (m(0, 2) + m(2, 0)) / s)
+        } else if m(1, 1) > m(2, 2) {
+            let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+            Quaternion::new((m(0, 2) - m(2, 0)) / s, (m(0, 1) + m(1, 0)) / s, s / 4.0, (m(1, 2) + m(2, 1)) / s)
+        } else {
+            let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+            Quaternion::new((m(1, 0) - m(0, 1)) / s, (m(0, 2) + m(2, 0)) / s, (m(1, 2) + m(2, 1)) / s, s / 4.0)
+        }
+    }
+    /// Rows of the rotation matrix.
+    fn to_rows(&self) -> [Vector; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        [
+            Vector::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)),
+            Vector::new(2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)),
+            Vector::new(2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)),
+        ]
+    }
+    fn scaled(&self, factor: f64) -> Quaternion {
+        Quaternion::new(self.w * factor, self.x * factor, self.y * factor, self.z * factor)
+    }
+    fn plus(&self, other: &Quaternion) -> Quaternion {
+        Quaternion::new(self.w + other.w, self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+    fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    /// Rotation part of the way to `other` at constant speed, along the shorter way round.
+    fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let (other, cos) = match self.dot(other) {
+            cos if cos < 0.0 => (other.scaled(-1.0), -cos),
+            cos => (other.clone(), cos),
+        };
+        if cos > 0.9995 {
+            // nearly the same rotation, where the sines below vanish
+            let blend = self.scaled(1.0 - t).plus(&other.scaled(t));
+            return blend.scaled(1.0 / blend.dot(&blend).sqrt());
+        }
+        let angle = cos.acos();
+        self.scaled(((1.0 - t) * angle).sin() / angle.sin()).plus(&other.scaled((t * angle).sin() / angle.sin()))
+    }
+}
+
+/// Product of two 3x3 matrices given by their rows.
+fn multiply(a: &[Vector; 3], b: &[Vector; 3]) -> [Vector; 3] {
+    let row = |r: &Vector| b[0].factor(r.get_x()).plus(&b[1].factor(r.get_y())).plus(&b[2].factor(r.get_z()));
+    [row(&a[0]), row(&a[1]), row(&a[2])]
+}
+
+fn transpose(a: &[Vector; 3]) -> [Vector; 3] {
+    let column = |j: usize| Vector::new(a[0].get_axis(j), a[1].get_axis(j), a[2].get_axis(j));
+    [column(0), column(1), column(2)]
+}
+
+/// Affine transformation split into a translation, a rotation and the stretch left over, which
+/// are applied in the reverse order. Blending them separately keeps rotating objects rigid.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Decomposition {
+    translation: Vector,
+    rotation: Quaternion,
+    /// Rows of the symmetric matrix that scales and shears the object before it is rotated.
+    stretch: [Vector; 3],
+}
+
+impl Decomposition {
+    /// Polar decomposition of the linear part after Shoemake and Duff, "Matrix Animation and
+    /// Polar Decomposition", which averages the matrix with its inverse transpose until it is a
+    /// rotation.
+    fn new(matrix: &Matrix4) -> Decomposition {
+        let linear = [0, 1, 2].map(|i| Vector::new(matrix.get(i, 0), matrix.get(i, 1), matrix.get(i, 2)));
+        let mut rotation = linear.clone();
+        for _ in 0..100 {
+            // rows of the inverse transpose are the cofactors over the determinant
+            let determinant = rotation[0].dot(&rotation[1].cross(&rotation[2]));
+            let cofactors = [
+                rotation[1].cross(&rotation[2]),
+                rotation[2].cross(&rotation[0]),
+                rotation[0].cross(&rotation[1]),
+            ];
+            let next = [0, 1, 2].map(|i| rotation[i].plus(&cofactors[i].factor(1.0 / determinant)).factor(0.5));
+            let change: f64 = (0..3).map(|i| next[i].minus(&rotation[i]).euclidian_distance()).sum();
+            rotation = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        // a mirroring transformation leaves a reflection, which is moved into the stretch
+        if rotation[0].dot(&rotation[1].cross(&rotation[2])) < 0.0 {
+            rotation = [0, 1, 2].map(|i| rotation[i].neg());
+        }
+        Decomposition {
+            translation: Vector::new(matrix.get(0, 3), matrix.get(1, 3), matrix.get(2, 3)),
+            stretch: multiply(&transpose(&rotation), &linear),
+            rotation: Quaternion::from_rows(&rotation),
+        }
+    }
+    /// Angle in radians the rotation turns by on the way to `other`, at most half a turn.
+    pub fn turn(&self, other: &Decomposition) -> f64 {
+        2.0 * self.rotation.dot(&other.rotation).abs().min(1.0).acos()
+    }
+    /// Transformation part of the way to `other`, `t = 0` gives this one and `t = 1` the other.
+    /// The rotation turns the shorter way round, so keyframes of a spinning object have to be less
+    /// than half a turn apart. `None` if the blend cannot be inverted, which only happens between
+    /// mirrored and unmirrored transformations.
+    pub fn interpolate(&self, other: &Decomposition, t: f64) -> Option<Transform> {
+        let translation = self.translation.plus(&other.translation.minus(&self.translation).factor(t));
+        let rotation = self.rotation.slerp(&other.rotation, t).to_rows();
+        let stretch = [0, 1, 2].map(|i| self.stretch[i].plus(&other.stretch[i].minus(&self.stretch[i]).factor(t)));
+        let linear = multiply(&rotation, &stretch);
+        let row = |i: usize| [linear[i].get_x(), linear[i].get_y(), linear[i].get_z(), translation.get_axis(i)];
+        Transform::new(Matrix4::new([row(0), row(1), row(2), [0.0, 0.0, 0.0, 1.0]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Vector, b: &Vector) {
+        assert!(a.minus(b).euclidian_distance() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_chaining_order() {
+        let transform = Transform::identity()
+            .scale(&Vector::new(2.0, 2.0, 2.0)).unwrap()
+            .rotate_z(90.0)
+            .translate(&Vector::new(0.0, 0.0, -5.0));
+        let point = transform.apply_point(&Vector::new(1.0, 0.0, 0.0));
+        assert_close(&point, &Vector::new(0.0, 2.0, -5.0));
+        assert_close(&transform.invert_point(&point), &Vector::new(1.0, 0.0, 0.0));
+        // directions ignore the translation
+        assert_close(&transform.apply_vector(&Vector::new(0.0, 1.0, 0.0)), &Vector::new(-2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let matrix = Matrix4::new([
+            [2.0, 1.0, 0.0, 3.0],
+            [0.0, 1.0, 4.0, -1.0],
+            [1.0, 0.0, 1.0, 2.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product = &matrix * &matrix.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.get(i, j) - expected).abs() < 1e-12);
+            }
+        }
+        assert!(Matrix4::scaling(&Vector::new(1.0, 0.0, 1.0)).inverse().is_none());
+        // a broken matrix gives a broken inverse instead of a panic
+        let broken = Matrix4::scaling(&Vector::new(f64::NAN, 1.0, 1.0));
+        assert!(broken.inverse().is_none_or(|inverse| inverse.get(0, 0).is_nan()));
+    }
+
+    #[test]
+    fn test_uniform_scale() {
+        let similar = Transform::identity()
+            .scale(&Vector::new(-2.0, 2.0, 2.0)).unwrap()
+            .rotate(&Vector::new(1.0, 1.0, 0.0).normalize(), 30.0)
+            .translate(&Vector::new(1.0, 2.0, 3.0));
+        assert!((similar.uniform_scale().unwrap() - 2.0).abs() < 1e-12);
+        let stretched = Transform::identity().scale(&Vector::new(1.0, 2.0, 1.0)).unwrap();
+        assert_eq!(stretched.uniform_scale(), None);
+    }
+
+    #[test]
+    fn test_normals_stay_perpendicular() {
+        let transform = Transform::identity().scale(&Vector::new(4.0, 1.0, 1.0)).unwrap();
+        // the surface x + y = 0 becomes x / 4 + y = 0
+        let tangent = transform.apply_vector(&Vector::new(1.0, -1.0, 0.0));
+        let normal = transform.apply_normal(&Vector::new(1.0, 1.0, 0.0));
+        assert!(tangent.dot(&normal).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_invert_ray_distance() {
+        let transform = Transform::identity().scale(&Vector::new(3.0, 3.0, 3.0)).unwrap();
+        let ray = Ray::from(Vector::new(0.0, 0.0, 9.0), Vector::new(0.0, 0.0, -1.0));
+        let (local, factor) = transform.invert_ray(&ray);
+        assert_close(local.get_origin(), &Vector::new(0.0, 0.0, 3.0));
+        // 2 units along the local ray are 6 along the world ray
+        assert!((2.0 / factor - 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let start = Transform::identity().translate(&Vector::new(2.0, 0.0, 0.0));
+        let end = Transform::identity().scale(&Vector::new(3.0, 3.0, 3.0)).unwrap()
+            .translate(&Vector::new(0.0, 4.0, 0.0));
+        let half_way = start.interpolate(&end, 0.5).unwrap();
+        assert_close(&half_way.apply_point(&Vector::new(1.0, 0.0, 0.0)), &Vector::new(3.0, 2.0, 0.0));
+        assert_close(&half_way.invert_point(&Vector::new(3.0, 2.0, 0.0)), &Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_interpolate_rotation() {
+        let start = Transform::identity().scale(&Vector::new(2.0, 1.0, 1.0)).unwrap();
+        let end = start.rotate_y(90.0).translate(&Vector::new(0.0, 0.0, -4.0));
+        // a rotating object keeps its size half way through
+        let half_way = start.interpolate(&end, 0.5).unwrap();
+        let expected = start.rotate_y(45.0).translate(&Vector::new(0.0, 0.0, -2.0));
+        for point in [Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0)].iter() {
+            assert_close(&half_way.apply_point(point), &expected.apply_point(point));
+        }
+        assert_close(&start.interpolate(&end, 1.0).unwrap().apply_point(&Vector::new(1.0, 2.0, 3.0)),
+                     &end.apply_point(&Vector::new(1.0, 2.0, 3.0)));
+        // half way through a half turn is a quarter turn
+        let quarter = Transform::identity().interpolate(&Transform::identity().rotate_y(180.0), 0.5).unwrap();
+        assert!((quarter.apply_point(&Vector::new(1.0, 0.0, 0.0)).euclidian_distance() - 1.0).abs() < 1e-9);
+        assert!(quarter.apply_point(&Vector::new(1.0, 0.0, 0.0)).get_x().abs() < 1e-9);
+        // mirrored keyframes blend their mirroring too
+        let mirrored = Transform::identity().scale(&Vector::new(-1.0, 1.0, 1.0)).unwrap().rotate_z(30.0);
+        assert_close(&mirrored.interpolate(&mirrored, 0.5).unwrap().apply_point(&Vector::new(1.0, 2.0, 3.0)),
+                     &mirrored.apply_point(&Vector::new(1.0, 2.0, 3.0)));
+    }
+}
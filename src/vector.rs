@@ -58,6 +58,27 @@ impl Vector {
     pub fn factor(&self, factor: f64) -> Vector {
         Vector::new(self.x * factor, self.y * factor, self.z * factor)
     }
+    pub fn min(&self, vec: &Vector) -> Vector {
+        Vector::new(self.x.min(vec.x), self.y.min(vec.y), self.z.min(vec.z))
+    }
+    pub fn max(&self, vec: &Vector) -> Vector {
+        Vector::new(self.x.max(vec.x), self.y.max(vec.y), self.z.max(vec.z))
+    }
+    /// Two unit vectors perpendicular to this one and to each other. The vector has to be normalized.
+    pub fn orthonormal_basis(&self) -> (Vector, Vector) {
+        let helper = if self.x.abs() > 0.9 { Vector::new(0.0, 1.0, 0.0) } else { Vector::new(1.0, 0.0, 0.0) };
+        let tangent = self.cross(&helper).normalize();
+        let bitangent = self.cross(&tangent);
+        (tangent, bitangent)
+    }
+    /// Returns the component along the given axis, 0 being x, 1 y and 2 z.
+    pub fn get_axis(&self, axis: usize) -> f64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
     pub fn get_x(&self) -> f64 {
         self.x
     }
@@ -89,6 +110,6 @@ impl ops::Neg for Vector {
     type Output = Vector;
 
     fn neg(self) -> Self::Output {
-        (&self).neg()
+        Vector::neg(&self)
     }
 }